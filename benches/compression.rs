@@ -0,0 +1,96 @@
+//! Throughput of packet compression for chunk-sized packets
+//!
+//! Run with `cargo bench --bench compression`, add `--features libdeflate`
+//! or `--features zlib-ng` to compare backends
+
+use rust_mc_proto::{
+    compression::{self, Compressor, Decompressor},
+    decode_packet_buffer, decode_packet_with, encode_packet, encode_packet_with, DataReader,
+    Packet, ProtocolError,
+};
+use std::{hint::black_box, io::Cursor, time::Instant};
+
+/// Compression threshold of vanilla server
+const THRESHOLD: usize = 256;
+
+/// Packets encoded in every run
+const PACKETS: usize = 500;
+
+/// Chunk-like packet data: few block states repeated with some noise
+fn chunk_data(size: usize, seed: u64) -> Vec<u8> {
+    let mut state = seed;
+    (0..size)
+        .map(|i| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            if state.is_multiple_of(8) { state as u8 } else { (i / 64 % 4) as u8 }
+        })
+        .collect()
+}
+
+fn frame_data(frame: &[u8]) -> Result<Vec<u8>, ProtocolError> {
+    let mut cursor = Cursor::new(frame);
+    let length = cursor.read_usize_varint()?;
+    cursor.read_bytes(length)
+}
+
+fn report(name: &str, size: usize, bytes: usize, start: Instant) {
+    let seconds = start.elapsed().as_secs_f64();
+    println!(
+        "{name:<26} {:>6} KiB {:>10.1} MB/s",
+        size / 1024,
+        bytes as f64 / seconds / 1_000_000.0
+    );
+}
+
+fn main() -> Result<(), ProtocolError> {
+    println!("backend: {}", compression::BACKEND);
+
+    for size in [16 * 1024, 32 * 1024, 64 * 1024] {
+        let packets = (0..8)
+            .map(|i| Packet::from_bytes(0x27, &chunk_data(size, i + 1)))
+            .collect::<Vec<_>>();
+        let bytes = size * PACKETS;
+
+        let start = Instant::now();
+        for i in 0..PACKETS {
+            black_box(encode_packet(Some(THRESHOLD), 6, &packets[i % packets.len()])?);
+        }
+        report("encode (new compressor)", size, bytes, start);
+
+        let mut compressor = Compressor::new(6);
+        let mut buf = Vec::new();
+        let start = Instant::now();
+        for i in 0..PACKETS {
+            buf.clear();
+            let packet = &packets[i % packets.len()];
+            encode_packet_with(&mut compressor, &mut buf, Some(THRESHOLD), packet)?;
+            black_box(&buf);
+        }
+        report("encode (reused)", size, bytes, start);
+
+        let frames = packets
+            .iter()
+            .map(|packet| frame_data(&encode_packet(Some(THRESHOLD), 6, packet)?))
+            .collect::<Result<Vec<_>, _>>()?;
+        let limits = Default::default();
+
+        let start = Instant::now();
+        for i in 0..PACKETS {
+            let frame = frames[i % frames.len()].clone();
+            black_box(decode_packet_buffer(frame, Some(THRESHOLD), &limits)?);
+        }
+        report("decode (new decompressor)", size, bytes, start);
+
+        let mut decompressor = Decompressor::new();
+        let start = Instant::now();
+        for i in 0..PACKETS {
+            let frame = frames[i % frames.len()].clone();
+            black_box(decode_packet_with(&mut decompressor, frame, Some(THRESHOLD), &limits)?);
+        }
+        report("decode (reused)", size, bytes, start);
+    }
+
+    Ok(())
+}
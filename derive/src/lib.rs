@@ -0,0 +1,277 @@
+//! Derive macros for `McRead`, `McWrite` and `McPacket` traits of rust_mc_proto
+//!
+//! Field attributes:
+//! - `#[mc(varint)]` - i32 field (or elements) is written as VarInt instead of Int
+//! - `#[mc(varlong)]` - i64 field (or elements) is written as VarLong instead of Long
+//! - `#[mc(prefixed)]` - Vec field is written as VarInt length and elements
+//! - `#[mc(optional)]` - Option field is written as Boolean and value if it is present
+//!
+//! Struct attributes:
+//! - `#[mc(id = 0x00)]` - packet id for `McPacket`
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, spanned::Spanned, Attribute, Data, DeriveInput, Error, Expr, Fields,
+    Index, Type,
+};
+
+#[derive(Clone, Copy)]
+enum Number {
+    VarInt,
+    VarLong,
+}
+
+#[derive(Default)]
+struct FieldAttrs {
+    number: Option<Number>,
+    prefixed: bool,
+    optional: bool,
+}
+
+fn parse_field_attrs(attrs: &[Attribute]) -> syn::Result<FieldAttrs> {
+    let mut result = FieldAttrs::default();
+
+    for attr in attrs.iter().filter(|i| i.path().is_ident("mc")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("varint") {
+                result.number = Some(Number::VarInt);
+            } else if meta.path.is_ident("varlong") {
+                result.number = Some(Number::VarLong);
+            } else if meta.path.is_ident("prefixed") {
+                result.prefixed = true;
+            } else if meta.path.is_ident("optional") {
+                result.optional = true;
+            } else {
+                return Err(meta.error("unknown mc field attribute"));
+            }
+            Ok(())
+        })?;
+    }
+
+    Ok(result)
+}
+
+fn parse_packet_id(attrs: &[Attribute]) -> syn::Result<Option<Expr>> {
+    let mut id = None;
+
+    for attr in attrs.iter().filter(|i| i.path().is_ident("mc")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("id") {
+                id = Some(meta.value()?.parse()?);
+                Ok(())
+            } else {
+                Err(meta.error("unknown mc struct attribute"))
+            }
+        })?;
+    }
+
+    Ok(id)
+}
+
+fn read_value(number: Option<Number>, ty: Option<&Type>) -> TokenStream2 {
+    match (number, ty) {
+        (Some(Number::VarInt), _) => quote!(::rust_mc_proto::DataReader::read_varint(reader)?),
+        (Some(Number::VarLong), _) => quote!(::rust_mc_proto::DataReader::read_varlong(reader)?),
+        (None, Some(ty)) => quote!(<#ty as ::rust_mc_proto::data::McRead>::read_from(reader)?),
+        (None, None) => quote!(::rust_mc_proto::data::McRead::read_from(reader)?),
+    }
+}
+
+fn read_field(attrs: &FieldAttrs, ty: &Type) -> TokenStream2 {
+    let mut value = if attrs.prefixed || attrs.optional {
+        read_value(attrs.number, None)
+    } else {
+        read_value(attrs.number, Some(ty))
+    };
+
+    if attrs.prefixed {
+        value = quote! {
+            ::rust_mc_proto::DataReader::read_prefixed_array(reader, |reader| {
+                let value = #value;
+                ::std::result::Result::Ok(value)
+            })?
+        };
+    }
+
+    if attrs.optional {
+        value = quote! {
+            ::rust_mc_proto::DataReader::read_optional(reader, |reader| {
+                let value = #value;
+                ::std::result::Result::Ok(value)
+            })?
+        };
+    }
+
+    value
+}
+
+fn write_value(number: Option<Number>, value: TokenStream2) -> TokenStream2 {
+    match number {
+        Some(Number::VarInt) => quote!(::rust_mc_proto::DataWriter::write_varint(writer, *#value)?;),
+        Some(Number::VarLong) => {
+            quote!(::rust_mc_proto::DataWriter::write_varlong(writer, *#value)?;)
+        }
+        None => quote!(::rust_mc_proto::data::McWrite::write_to(#value, writer)?;),
+    }
+}
+
+fn write_field(attrs: &FieldAttrs, value: TokenStream2) -> TokenStream2 {
+    let item = format_ident!("item");
+
+    let inner = |value: TokenStream2| {
+        if attrs.prefixed {
+            let element = write_value(attrs.number, quote!(#item));
+            quote! {
+                ::rust_mc_proto::DataWriter::write_prefixed_array(writer, #value, |writer, #item| {
+                    #element
+                    ::std::result::Result::Ok(())
+                })?;
+            }
+        } else {
+            write_value(attrs.number, value)
+        }
+    };
+
+    if attrs.optional {
+        let inner = inner(quote!(value));
+        quote! {
+            ::rust_mc_proto::DataWriter::write_optional(writer, #value.as_ref(), |writer, value| {
+                #inner
+                ::std::result::Result::Ok(())
+            })?;
+        }
+    } else {
+        inner(value)
+    }
+}
+
+fn struct_fields(input: &DeriveInput) -> syn::Result<&Fields> {
+    match &input.data {
+        Data::Struct(data) => Ok(&data.fields),
+        _ => Err(Error::new(input.span(), "only structs are supported")),
+    }
+}
+
+fn expand_read(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = struct_fields(input)?;
+
+    let body = match fields {
+        Fields::Named(fields) => {
+            let mut values = Vec::new();
+            for field in &fields.named {
+                let name = &field.ident;
+                let value = read_field(&parse_field_attrs(&field.attrs)?, &field.ty);
+                values.push(quote!(#name: #value));
+            }
+            quote!(Self { #(#values),* })
+        }
+        Fields::Unnamed(fields) => {
+            let mut values = Vec::new();
+            for field in &fields.unnamed {
+                values.push(read_field(&parse_field_attrs(&field.attrs)?, &field.ty));
+            }
+            quote!(Self(#(#values),*))
+        }
+        Fields::Unit => quote!(Self),
+    };
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::rust_mc_proto::data::McRead for #name #ty_generics #where_clause {
+            fn read_from<R: ::rust_mc_proto::DataReader + ?Sized>(
+                reader: &mut R,
+            ) -> ::std::result::Result<Self, ::rust_mc_proto::ProtocolError> {
+                ::std::result::Result::Ok(#body)
+            }
+        }
+    })
+}
+
+fn expand_write(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = struct_fields(input)?;
+
+    let mut writes = Vec::new();
+    for (i, field) in fields.iter().enumerate() {
+        let value = match &field.ident {
+            Some(name) => quote!((&self.#name)),
+            None => {
+                let index = Index::from(i);
+                quote!((&self.#index))
+            }
+        };
+        writes.push(write_field(&parse_field_attrs(&field.attrs)?, value));
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::rust_mc_proto::data::McWrite for #name #ty_generics #where_clause {
+            fn write_to<W: ::rust_mc_proto::DataWriter + ?Sized>(
+                &self,
+                writer: &mut W,
+            ) -> ::std::result::Result<(), ::rust_mc_proto::ProtocolError> {
+                #(#writes)*
+                ::std::result::Result::Ok(())
+            }
+        }
+    })
+}
+
+fn expand_packet(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let id = parse_packet_id(&input.attrs)?
+        .ok_or_else(|| Error::new(input.span(), "missing #[mc(id = ...)] attribute"))?;
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::rust_mc_proto::packets::McPacket for #name #ty_generics #where_clause {
+            const ID: i32 = #id;
+
+            fn encode(
+                &self,
+                packet: &mut ::rust_mc_proto::Packet,
+            ) -> ::std::result::Result<(), ::rust_mc_proto::ProtocolError> {
+                ::rust_mc_proto::data::McWrite::write_to(self, packet)
+            }
+
+            fn decode(
+                packet: &mut ::rust_mc_proto::Packet,
+            ) -> ::std::result::Result<Self, ::rust_mc_proto::ProtocolError> {
+                ::rust_mc_proto::data::McRead::read_from(packet)
+            }
+        }
+    })
+}
+
+/// Derive `McRead`, reads fields in declaration order
+#[proc_macro_derive(McRead, attributes(mc))]
+pub fn derive_mc_read(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_read(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Derive `McWrite`, writes fields in declaration order
+#[proc_macro_derive(McWrite, attributes(mc))]
+pub fn derive_mc_write(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_write(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Derive `McPacket` with id from `#[mc(id = ...)]`, type must implement `McRead` and `McWrite`
+#[proc_macro_derive(McPacket, attributes(mc))]
+pub fn derive_mc_packet(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_packet(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
@@ -1,4 +1,10 @@
-use rust_mc_proto::{prelude::*, MCConnTcp, Packet, ProtocolError};
+use rust_mc_proto::{
+    packets::{
+        handshake::{Handshake, NextState},
+        status::{StatusRequest, StatusResponse},
+    },
+    status::ServerStatus, MCConnTcp, ProtocolError,
+};
 
 /*
 
@@ -9,34 +15,38 @@ use rust_mc_proto::{prelude::*, MCConnTcp, Packet, ProtocolError};
 
 fn send_handshake(
     conn: &mut MCConnTcp,
-    protocol_version: u16,
+    protocol_version: i32,
     server_address: &str,
     server_port: u16,
-    next_state: u8
+    next_state: NextState
 ) -> Result<(), ProtocolError> {
-    conn.write_packet(&Packet::build(0x00, |packet| {
-        packet.write_u16_varint(protocol_version)?;
-        packet.write_string(server_address)?;
-        packet.write_unsigned_short(server_port)?;
-        packet.write_u8_varint(next_state)
-    })?)
+    conn.send(&Handshake {
+        protocol_version,
+        server_address: server_address.to_string(),
+        server_port,
+        next_state,
+    })
 }
 
 fn send_status_request(conn: &mut MCConnTcp) -> Result<(), ProtocolError> {
-    conn.write_packet(&Packet::empty(0x00))
+    conn.send(&StatusRequest)
 }
 
-fn read_status_response(conn: &mut MCConnTcp) -> Result<String, ProtocolError> {
-    conn.read_packet()?.read_string()
+fn read_status_response(conn: &mut MCConnTcp) -> Result<ServerStatus, ProtocolError> {
+    conn.recv::<StatusResponse>()?.status()
 }
 
 fn main() {
     let mut conn = MCConnTcp::connect("mc.hypixel.net:25565").unwrap();
 
-    send_handshake(&mut conn, 765, "mc.hypixel.net", 25565, 1).unwrap();
+    send_handshake(&mut conn, 765, "mc.hypixel.net", 25565, NextState::Status).unwrap();
     send_status_request(&mut conn).unwrap();
 
-    let motd = read_status_response(&mut conn).unwrap();
+    let status = read_status_response(&mut conn).unwrap();
 
-    dbg!(motd);
+    println!("{} ({})", status.version.name, status.version.protocol);
+    if let Some(players) = &status.players {
+        println!("{}/{} players", players.online, players.max);
+    }
+    println!("{}", status.description.to_legacy());
 }
@@ -12,5 +12,7 @@ fn main() -> Result<(), ProtocolError> {
 
     conn.write_packet(&Packet::empty(0x00))?; // status request packet
 
-    Ok(println!("motd: {}", conn.read_packet()?.read_string()?)) // status response packet
+    println!("motd: {}", conn.read_packet()?.read_string()?); // status response packet
+
+    Ok(())
 }
@@ -0,0 +1,288 @@
+//! Async Minecraft connection based on tokio
+
+use crate::{
+    buffer::ReadBuffer,
+    compression::{Compressor, Decompressor},
+    decode_packet_with, encode_packet_with,
+    encryption::{self, Decryptor, Encryptor},
+    limits::Limits,
+    packet::{PacketBuffer, PacketData},
+    packets::AnyPacket,
+    registry::{ProtocolRegistry, RegistryPacket},
+    state::{ConnectionState, Side, Tracker},
+    McPacket, Packet, ProtocolError,
+};
+use std::sync::Arc;
+use tokio::{
+    io::{AsyncRead, AsyncWrite, AsyncWriteExt},
+    net::{TcpStream, ToSocketAddrs},
+};
+
+/// Async Minecraft connection, wrapper for stream with compression and encryption
+pub struct AsyncMinecraftConnection<T: AsyncRead + AsyncWrite + Unpin> {
+    stream: T,
+    encryptor: Option<Encryptor>,
+    decryptor: Option<Decryptor>,
+    compression: Option<usize>,
+    compressor: Compressor,
+    decompressor: Decompressor,
+    is_alive: bool,
+    tracker: Tracker,
+    limits: Limits,
+    read_buffer: ReadBuffer,
+}
+
+impl AsyncMinecraftConnection<TcpStream> {
+    /// Connect to Minecraft Server with TcpStream
+    pub async fn connect(
+        addr: impl ToSocketAddrs,
+    ) -> Result<AsyncMinecraftConnection<TcpStream>, ProtocolError> {
+        match TcpStream::connect(addr).await {
+            Ok(stream) => Ok(AsyncMinecraftConnection::new(stream)),
+            Err(e) => Err(ProtocolError::StreamConnectError(e)),
+        }
+    }
+}
+
+impl<T: AsyncRead + AsyncWrite + Unpin> AsyncMinecraftConnection<T> {
+    /// Create new AsyncMinecraftConnection from stream
+    pub fn new(stream: T) -> AsyncMinecraftConnection<T> {
+        AsyncMinecraftConnection {
+            stream,
+            encryptor: None,
+            decryptor: None,
+            compression: None,
+            compressor: Compressor::new(1),
+            decompressor: Decompressor::new(),
+            is_alive: true,
+            tracker: Tracker::default(),
+            limits: Limits::default(),
+            read_buffer: ReadBuffer::default(),
+        }
+    }
+
+    /// Close stream
+    pub async fn close(&mut self) {
+        let _ = self.stream.shutdown().await;
+        self.is_alive = false;
+    }
+
+    /// Is connection alive
+    pub fn is_alive(&self) -> bool {
+        self.is_alive
+    }
+
+    /// Set compression threshold
+    pub fn set_compression(&mut self, threshold: Option<usize>) {
+        self.compression = threshold;
+    }
+
+    /// Get compression threshold
+    pub fn compression(&self) -> Option<usize> {
+        self.compression
+    }
+
+    /// Set compression type
+    ///
+    /// `compression_type` is integer from 0 (none) to 9 (longest)
+    /// 1 is fast compression
+    /// 6 is normal compression
+    pub fn set_compression_type(&mut self, compression_type: u32) {
+        self.compressor.set_level(compression_type);
+    }
+
+    /// Set limits of received data
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    /// Get limits of received data
+    pub fn limits(&self) -> Limits {
+        self.limits
+    }
+
+    /// Get compression type
+    ///
+    /// `compression_type` is integer from 0 (none) to 9 (longest)
+    /// 1 is fast compression
+    /// 6 is normal compression
+    pub fn compression_type(&self) -> u32 {
+        self.compressor.level()
+    }
+
+    /// Enable AES-128-CFB8 encryption with shared secret
+    ///
+    /// Everything read and written after this call is encrypted,
+    /// `shared_secret` must be 16 bytes long
+    pub fn enable_encryption(&mut self, shared_secret: &[u8]) -> Result<(), ProtocolError> {
+        let (encryptor, mut decryptor) = encryption::new_cipher(shared_secret)?;
+
+        // bytes after packet that enabled encryption may be already read
+        encryption::decrypt(&mut decryptor, self.read_buffer.available_mut());
+
+        self.encryptor = Some(encryptor);
+        self.decryptor = Some(decryptor);
+        Ok(())
+    }
+
+    /// Is encryption enabled
+    pub fn is_encrypted(&self) -> bool {
+        self.encryptor.is_some()
+    }
+
+    /// Get protocol state
+    pub fn state(&self) -> ConnectionState {
+        self.tracker.state
+    }
+
+    /// Set protocol state, for connections that don't start from handshake
+    pub fn set_state(&mut self, state: ConnectionState) {
+        self.tracker.state = state;
+    }
+
+    /// Get side of connection, `None` until handshake is read or written
+    pub fn side(&self) -> Option<Side> {
+        self.tracker.side
+    }
+
+    /// Set side of connection
+    pub fn set_side(&mut self, side: Side) {
+        self.tracker.side = Some(side);
+    }
+
+    /// Get protocol version, `None` until handshake is read or written
+    pub fn protocol_version(&self) -> Option<i32> {
+        self.tracker.protocol_version
+    }
+
+    /// Set protocol version, for connections that don't start from handshake
+    pub fn set_protocol_version(&mut self, protocol_version: i32) {
+        self.tracker.protocol_version = Some(protocol_version);
+    }
+
+    /// Get registry of packet ids
+    pub fn registry(&self) -> Option<Arc<ProtocolRegistry>> {
+        self.tracker.registry.clone()
+    }
+
+    /// Set registry of packet ids, used for state transitions and
+    /// [`send_versioned`](Self::send_versioned) / [`recv_versioned`](Self::recv_versioned)
+    pub fn set_registry(&mut self, registry: Arc<ProtocolRegistry>) {
+        self.tracker.registry = Some(registry);
+    }
+
+    /// Get mutable reference of stream
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.stream
+    }
+
+    /// Get immutable reference of stream
+    pub fn get_ref(&self) -> &T {
+        &self.stream
+    }
+
+    /// Read [`Packet`](Packet) from connection, updates [`state`](Self::state)
+    pub async fn read_packet(&mut self) -> Result<Packet, ProtocolError> {
+        self.read_frame().await
+    }
+
+    /// Read [`Packet`](Packet) which data shares memory with read buffer, updates
+    /// [`state`](Self::state)
+    ///
+    /// Data of uncompressed packets is not copied, so packet can be forwarded without copying
+    #[cfg(feature = "bytes")]
+    pub async fn read_packet_bytes(&mut self) -> Result<Packet<bytes::BytesMut>, ProtocolError> {
+        self.read_frame().await
+    }
+
+    async fn read_frame<B>(&mut self) -> Result<Packet<B>, ProtocolError>
+    where
+        B: PacketData + From<PacketBuffer>,
+    {
+        if !self.is_alive {
+            return Err(ProtocolError::ConnectionClosedError);
+        }
+
+        let frame = match self
+            .read_buffer
+            .read_frame_async(&mut self.stream, &mut self.decryptor, self.limits.max_frame_length)
+            .await
+        {
+            Err(ProtocolError::ConnectionClosedError) => {
+                self.is_alive = false;
+                return Err(ProtocolError::ConnectionClosedError);
+            }
+            i => i?,
+        };
+
+        let packet =
+            decode_packet_with(&mut self.decompressor, frame, self.compression, &self.limits)?;
+        self.tracker.update(false, &packet);
+        Ok(packet)
+    }
+
+    /// Write [`Packet`](Packet) to connection, updates [`state`](Self::state)
+    pub async fn write_packet<B: PacketData>(
+        &mut self,
+        packet: &Packet<B>,
+    ) -> Result<(), ProtocolError> {
+        if !self.is_alive {
+            return Err(ProtocolError::ConnectionClosedError);
+        }
+
+        let mut buf = Vec::new();
+        encode_packet_with(&mut self.compressor, &mut buf, self.compression, packet)?;
+
+        if let Some(encryptor) = self.encryptor.as_mut() {
+            encryption::encrypt(encryptor, &mut buf);
+        }
+
+        self.stream
+            .write_all(&buf)
+            .await
+            .map_err(ProtocolError::WriteError)?;
+        self.tracker.update(true, packet);
+        Ok(())
+    }
+
+    /// Write typed packet to connection
+    pub async fn send<P: McPacket>(&mut self, packet: &P) -> Result<(), ProtocolError> {
+        self.write_packet(&packet.to_packet()?).await
+    }
+
+    /// Read typed packet from connection, fails if packet has other id
+    pub async fn recv<P: McPacket>(&mut self) -> Result<P, ProtocolError> {
+        P::from_packet(self.read_packet().await?)
+    }
+
+    /// Read packet and decode it by current state and side
+    ///
+    /// Side is server if it is not known yet, because only client sends handshake.
+    /// Ids are taken from registry if connection has one
+    pub async fn recv_any(&mut self) -> Result<AnyPacket, ProtocolError> {
+        let tracker = self.tracker.clone();
+        let packet = self.read_packet().await?;
+        tracker.decode_any(self.tracker.inbound(), packet)
+    }
+
+    /// Write typed packet with id of connection protocol version, see [`set_registry`](Self::set_registry)
+    pub async fn send_versioned<P: RegistryPacket>(
+        &mut self,
+        packet: &P,
+    ) -> Result<(), ProtocolError> {
+        let packet = self.tracker.encode(packet)?;
+        self.write_packet(&packet).await
+    }
+
+    /// Read typed packet with id of connection protocol version, see [`set_registry`](Self::set_registry)
+    pub async fn recv_versioned<P: RegistryPacket>(&mut self) -> Result<P, ProtocolError> {
+        let packet = self.read_packet().await?;
+        self.tracker.decode(packet)
+    }
+}
+
+/// AsyncMinecraftConnection shorter alias
+pub type AsyncMCConn<T> = AsyncMinecraftConnection<T>;
+
+/// AsyncMinecraftConnection\<TcpStream\> shorter alias
+pub type AsyncMCConnTcp = AsyncMinecraftConnection<TcpStream>;
@@ -0,0 +1,267 @@
+//! Read buffer of connection, frames are parsed from buffered bytes
+//! instead of reading stream byte by byte
+
+use crate::{
+    encryption::{self, CipherCell, Decryptor},
+    limits::check_length,
+    packet::{PacketBuffer, PacketData},
+    DataReader, ProtocolError,
+};
+use std::io::{self, Cursor, ErrorKind, Read};
+
+#[cfg(feature = "tokio")]
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+#[cfg(feature = "atomic_clone")]
+use std::sync::{Arc, Mutex};
+
+/// Min count of bytes that is read from stream at once
+pub(crate) const READ_CHUNK: usize = 8192;
+
+/// Decrypted bytes that are read from stream but not consumed yet
+///
+/// With `bytes` feature consumed bytes are removed from start of buffer and
+/// frames are split from it without copying, so `pos` is always 0
+#[derive(Debug, Default)]
+pub(crate) struct ReadBuffer {
+    buf: PacketBuffer,
+    pos: usize,
+}
+
+impl ReadBuffer {
+    /// Bytes that are not consumed yet
+    pub fn available_mut(&mut self) -> &mut [u8] {
+        &mut self.buf[self.pos..]
+    }
+
+    fn available(&self) -> &[u8] {
+        &self.buf[self.pos..]
+    }
+
+    fn consume(&mut self, size: usize) {
+        #[cfg(feature = "bytes")]
+        self.buf.advance(size);
+
+        #[cfg(not(feature = "bytes"))]
+        {
+            self.pos += size;
+            if self.pos == self.buf.len() {
+                self.buf.clear();
+                self.pos = 0;
+            }
+        }
+    }
+
+    /// Take `size` bytes, without copying with `bytes` feature
+    fn take(&mut self, size: usize) -> PacketBuffer {
+        #[cfg(feature = "bytes")]
+        {
+            self.buf.split_to(size)
+        }
+
+        #[cfg(not(feature = "bytes"))]
+        {
+            let bytes = self.available()[..size].to_vec();
+            self.consume(size);
+            bytes
+        }
+    }
+
+    /// Remove consumed bytes from start of buffer, returns count of available bytes
+    fn compact(&mut self) -> usize {
+        if self.pos != 0 {
+            self.buf.advance(self.pos);
+            self.pos = 0;
+        }
+        self.buf.len()
+    }
+
+    /// Grow buffer to read at least `size` available bytes, returns start of free space
+    fn reserve(&mut self, size: usize) -> usize {
+        let start = self.compact();
+        self.buf.resize(start + (size - start).max(READ_CHUNK), 0);
+        start
+    }
+
+    /// Keep bytes that are read to free space at `start` and decrypt them
+    fn commit(
+        &mut self,
+        start: usize,
+        read: io::Result<usize>,
+        decrypt: impl FnOnce(&mut [u8]),
+    ) -> Result<(), ProtocolError> {
+        match read {
+            Ok(0) => {
+                self.buf.truncate(start);
+                Err(ProtocolError::ConnectionClosedError)
+            }
+            Ok(read) => {
+                self.buf.truncate(start + read);
+                decrypt(&mut self.buf[start..]);
+                Ok(())
+            }
+            Err(e) => {
+                self.buf.truncate(start);
+                Err(ProtocolError::ReadError { field: None, source: e })
+            }
+        }
+    }
+
+    /// Read stream until at least `size` bytes are available
+    fn fill<T: Read>(
+        &mut self,
+        stream: &mut T,
+        decryptor: &mut CipherCell<Decryptor>,
+        size: usize,
+    ) -> Result<(), ProtocolError> {
+        while self.available().len() < size {
+            let start = self.reserve(size);
+            let read = loop {
+                match stream.read(&mut self.buf[start..]) {
+                    Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                    i => break i,
+                }
+            };
+            self.commit(start, read, |i| encryption::decrypt_cell(decryptor, i))?;
+        }
+        Ok(())
+    }
+
+    /// Read async stream until at least `size` bytes are available
+    ///
+    /// Bytes are read to spare capacity, so length of buffer grows only by bytes
+    /// that are read and future can be dropped at any await
+    #[cfg(feature = "tokio")]
+    async fn fill_async<T: AsyncRead + Unpin>(
+        &mut self,
+        stream: &mut T,
+        decryptor: &mut Option<Decryptor>,
+        size: usize,
+    ) -> Result<(), ProtocolError> {
+        while self.available().len() < size {
+            let start = self.compact();
+            self.buf.reserve((size - start).max(READ_CHUNK));
+            let read = loop {
+                match stream.read_buf(&mut self.buf).await {
+                    Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                    i => break i,
+                }
+            };
+            self.commit(start, read, |i| {
+                if let Some(decryptor) = decryptor.as_mut() {
+                    encryption::decrypt(decryptor, i);
+                }
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Length of next frame and size of its VarInt, `None` if VarInt is not read fully yet
+    fn frame_length(&self) -> Result<Option<(usize, usize)>, ProtocolError> {
+        match Cursor::new(self.available()).read_usize_varint_size() {
+            Err(ProtocolError::ConnectionClosedError) => Ok(None),
+            i => i.map(Some),
+        }
+    }
+
+    /// Read `size` bytes
+    pub fn read_bytes<T: Read>(
+        &mut self,
+        stream: &mut T,
+        decryptor: &mut CipherCell<Decryptor>,
+        size: usize,
+    ) -> Result<Vec<u8>, ProtocolError> {
+        self.fill(stream, decryptor, size)?;
+        let bytes = self.available()[..size].to_vec();
+        self.consume(size);
+        Ok(bytes)
+    }
+
+    /// Read frame without length prefix and pass it to `decode`,
+    /// fails before frame is read if it is longer than `max_length`
+    ///
+    /// Frame is copied only if it is `Vec<u8>` while buffer is `BytesMut`
+    pub fn read_frame<T: Read, B: From<PacketBuffer>, R>(
+        &mut self,
+        stream: &mut T,
+        decryptor: &mut CipherCell<Decryptor>,
+        max_length: usize,
+        decode: impl FnOnce(B) -> Result<R, ProtocolError>,
+    ) -> Result<R, ProtocolError> {
+        let (length, length_size) = loop {
+            match self.frame_length()? {
+                Some(i) => break i,
+                None => {
+                    let size = self.available().len() + 1;
+                    self.fill(stream, decryptor, size)?;
+                }
+            }
+        };
+        check_length("Frame", length, max_length)?;
+
+        self.fill(stream, decryptor, length_size + length)?;
+        self.consume(length_size);
+        decode(B::from(self.take(length)))
+    }
+
+    /// Read frame from async stream without length prefix,
+    /// fails before frame is read if it is longer than `max_length`
+    #[cfg(feature = "tokio")]
+    pub async fn read_frame_async<T: AsyncRead + Unpin, B: From<PacketBuffer>>(
+        &mut self,
+        stream: &mut T,
+        decryptor: &mut Option<Decryptor>,
+        max_length: usize,
+    ) -> Result<B, ProtocolError> {
+        let (length, length_size) = loop {
+            match self.frame_length()? {
+                Some(i) => break i,
+                None => {
+                    let size = self.available().len() + 1;
+                    self.fill_async(stream, decryptor, size).await?;
+                }
+            }
+        };
+        check_length("Frame", length, max_length)?;
+
+        self.fill_async(stream, decryptor, length_size + length).await?;
+        self.consume(length_size);
+        Ok(B::from(self.take(length)))
+    }
+}
+
+/// Read buffer shared between clones of connection
+#[cfg(feature = "atomic_clone")]
+pub(crate) type BufferCell = Arc<Mutex<ReadBuffer>>;
+
+/// Read buffer owned by connection
+#[cfg(not(feature = "atomic_clone"))]
+pub(crate) type BufferCell = ReadBuffer;
+
+pub(crate) fn new_cell() -> BufferCell {
+    BufferCell::default()
+}
+
+/// Read buffer of cloned connection, shared with `atomic_clone` feature and empty without it
+pub(crate) fn clone_cell(cell: &BufferCell) -> BufferCell {
+    #[cfg(feature = "atomic_clone")]
+    {
+        cell.clone()
+    }
+    #[cfg(not(feature = "atomic_clone"))]
+    {
+        let _ = cell;
+        ReadBuffer::default()
+    }
+}
+
+pub(crate) fn with_cell<R>(cell: &mut BufferCell, f: impl FnOnce(&mut ReadBuffer) -> R) -> R {
+    #[cfg(feature = "atomic_clone")]
+    {
+        f(&mut cell.lock().unwrap())
+    }
+    #[cfg(not(feature = "atomic_clone"))]
+    {
+        f(cell)
+    }
+}
@@ -0,0 +1,225 @@
+//! Zlib compression of packets with state that is reused between packets
+//!
+//! Backend is chosen by features: `libdeflate` uses libdeflate, otherwise flate2 is used
+//! (with its Rust backend, or with zlib-ng if `zlib-ng` feature is enabled)
+
+use crate::{limits::check_length, ProtocolError};
+use flate2::{Decompress, FlushDecompress, Status};
+use std::io;
+
+#[cfg(not(feature = "libdeflate"))]
+use flate2::{Compress, Compression, FlushCompress};
+
+/// Name of compression backend
+#[cfg(feature = "libdeflate")]
+pub const BACKEND: &str = "libdeflate";
+
+/// Name of compression backend
+#[cfg(all(feature = "zlib-ng", not(feature = "libdeflate")))]
+pub const BACKEND: &str = "zlib-ng";
+
+/// Name of compression backend
+#[cfg(not(any(feature = "zlib-ng", feature = "libdeflate")))]
+pub const BACKEND: &str = "miniz_oxide";
+
+/// Bytes that output buffer grows by when it is full
+const GROW_SIZE: usize = 4096;
+
+/// Packet compressor, keeps backend state and output buffer between packets
+pub struct Compressor {
+    level: u32,
+    #[cfg(not(feature = "libdeflate"))]
+    state: Option<Compress>,
+    #[cfg(feature = "libdeflate")]
+    state: Option<libdeflater::Compressor>,
+    #[cfg(feature = "libdeflate")]
+    input: Vec<u8>,
+    output: Vec<u8>,
+}
+
+impl Compressor {
+    /// Create compressor, `level` is integer from 0 (none) to 9 (longest)
+    ///
+    /// Backend state is allocated on first compression
+    pub fn new(level: u32) -> Compressor {
+        Compressor {
+            level,
+            state: None,
+            #[cfg(feature = "libdeflate")]
+            input: Vec::new(),
+            output: Vec::new(),
+        }
+    }
+
+    /// Get compression level
+    pub fn level(&self) -> u32 {
+        self.level
+    }
+
+    /// Set compression level, backend state is allocated again if level is changed
+    pub fn set_level(&mut self, level: u32) {
+        if self.level != level {
+            self.level = level;
+            self.state = None;
+        }
+    }
+
+    /// Compress concatenation of `parts`, result is valid until next call
+    #[cfg(not(feature = "libdeflate"))]
+    pub fn compress(&mut self, parts: &[&[u8]]) -> Result<&[u8], ProtocolError> {
+        let level = self.level;
+        let state = self
+            .state
+            .get_or_insert_with(|| Compress::new(Compression::new(level), true));
+        state.reset();
+        self.output.clear();
+
+        for part in parts {
+            deflate(state, part, &mut self.output, FlushCompress::None)?;
+        }
+        deflate(state, &[], &mut self.output, FlushCompress::Finish)?;
+
+        Ok(&self.output)
+    }
+
+    /// Compress concatenation of `parts`, result is valid until next call
+    #[cfg(feature = "libdeflate")]
+    pub fn compress(&mut self, parts: &[&[u8]]) -> Result<&[u8], ProtocolError> {
+        let data = match parts {
+            [data] => *data,
+            parts => {
+                self.input.clear();
+                for part in parts {
+                    self.input.extend_from_slice(part);
+                }
+                &self.input[..]
+            }
+        };
+
+        let level = self.level;
+        let state = self.state.get_or_insert_with(|| {
+            libdeflater::Compressor::new(
+                libdeflater::CompressionLvl::new(level as i32).unwrap_or_default(),
+            )
+        });
+
+        self.output.resize(state.zlib_compress_bound(data.len()), 0);
+        let size = state
+            .zlib_compress(data, &mut self.output)
+            .map_err(|e| ProtocolError::ZlibError(io::Error::other(e)))?;
+        self.output.truncate(size);
+
+        Ok(&self.output)
+    }
+}
+
+/// Packet decompressor, keeps backend state between packets
+#[derive(Default)]
+pub struct Decompressor {
+    state: Option<Decompress>,
+    #[cfg(feature = "libdeflate")]
+    libdeflate: Option<libdeflater::Decompressor>,
+}
+
+impl Decompressor {
+    /// Create decompressor, backend state is allocated on first decompression
+    pub fn new() -> Decompressor {
+        Decompressor::default()
+    }
+
+    /// Decompress data of declared `length`, fails if it is longer than `max_length`
+    ///
+    /// Result can have other length than declared if data has wrong length
+    pub fn decompress(
+        &mut self,
+        data: &[u8],
+        length: usize,
+        max_length: usize,
+    ) -> Result<Vec<u8>, ProtocolError> {
+        check_length("Decompressed Packet", length, max_length)?;
+        let mut output = Vec::new();
+
+        #[cfg(feature = "libdeflate")]
+        {
+            output.resize(length, 0);
+            let state = self.libdeflate.get_or_insert_with(libdeflater::Decompressor::new);
+            match state.zlib_decompress(data, &mut output) {
+                Ok(size) => {
+                    output.truncate(size);
+                    return Ok(output);
+                }
+                // data is longer than declared, decompress it with flate2 below
+                Err(libdeflater::DecompressionError::InsufficientSpace) => output.clear(),
+                Err(e) => return Err(ProtocolError::ZlibError(io::Error::other(e))),
+            }
+        }
+
+        let state = self.state.get_or_insert_with(|| Decompress::new(true));
+        state.reset(true);
+
+        let limit = max_length.saturating_add(1);
+        let mut data = data;
+        loop {
+            let written = output.len();
+            if written == limit {
+                return Err(ProtocolError::LengthLimitError {
+                    field: "Decompressed Packet",
+                    length: written,
+                    max: max_length,
+                });
+            }
+            // declared length first, then grow if data is longer
+            let size = if written == 0 && length != 0 {
+                length
+            } else {
+                written + written.max(GROW_SIZE)
+            };
+            output.resize(size.min(limit), 0);
+
+            let (total_in, total_out) = (state.total_in(), state.total_out());
+            let status = state
+                .decompress(data, &mut output[written..], FlushDecompress::None)
+                .map_err(|e| ProtocolError::ZlibError(e.into()))?;
+            data = &data[(state.total_in() - total_in) as usize..];
+            output.truncate(written + (state.total_out() - total_out) as usize);
+
+            match status {
+                Status::StreamEnd => {
+                    check_length("Decompressed Packet", output.len(), max_length)?;
+                    return Ok(output);
+                }
+                Status::BufError if output.len() == written => {
+                    return Err(ProtocolError::ZlibError(io::ErrorKind::UnexpectedEof.into()));
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Compress `input` to spare capacity of `output`, growing it when it is full
+#[cfg(not(feature = "libdeflate"))]
+fn deflate(
+    state: &mut Compress,
+    mut input: &[u8],
+    output: &mut Vec<u8>,
+    flush: FlushCompress,
+) -> Result<(), ProtocolError> {
+    loop {
+        if output.capacity() - output.len() < GROW_SIZE {
+            output.reserve(output.capacity().max(GROW_SIZE));
+        }
+
+        let total_in = state.total_in();
+        let status = state
+            .compress_vec(input, output, flush)
+            .map_err(|e| ProtocolError::ZlibError(e.into()))?;
+        input = &input[(state.total_in() - total_in) as usize..];
+
+        match (status, flush) {
+            (Status::StreamEnd, _) => return Ok(()),
+            (_, FlushCompress::None) if input.is_empty() => return Ok(()),
+            _ => {}
+        }
+    }
+}
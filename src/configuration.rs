@@ -0,0 +1,109 @@
+//! Configuration state helper, server side
+//!
+//! Server sends Feature Flags and Known Packs, client answers with packs that it has.
+//! Then server sends Registry Data of every registry, Update Tags and Finish Configuration,
+//! client answers with Acknowledge Finish Configuration and connection goes to Play state.
+//!
+//! Only protocol versions since 1.20.5 are supported, registries must be provided by caller,
+//! for example from data generator of vanilla server. Packets of Play state, like Login,
+//! are not sent
+
+use crate::{
+    packets::{
+        configuration::{
+            ClientboundKnownPacks, FeatureFlags, FinishConfiguration, KnownPack, RegistryData,
+            RegistryTags, UpdateTags,
+        },
+        AnyPacket,
+    },
+    registry::PROTOCOL_1_20_5,
+    MinecraftConnection, ProtocolError,
+};
+use std::io::{Read, Write};
+
+/// Registries, tags and features sent to client in configuration state
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RegistrySet {
+    /// Enabled features, like `minecraft:vanilla`
+    pub feature_flags: Vec<String>,
+    /// Packs that entries without data are taken from
+    pub known_packs: Vec<KnownPack>,
+    pub registries: Vec<RegistryData>,
+    pub tags: Vec<RegistryTags>,
+}
+
+impl RegistrySet {
+    /// Create empty registry set
+    pub fn new() -> RegistrySet {
+        RegistrySet::default()
+    }
+
+    /// Known packs that entries without data are taken from, client must have them
+    ///
+    /// Pack of entry is found by namespace of its id
+    pub fn required_packs(&self) -> Vec<&KnownPack> {
+        let namespaces = self
+            .registries
+            .iter()
+            .flat_map(|i| &i.entries)
+            .filter(|i| i.data.is_none())
+            .map(|i| i.id.split_once(':').map_or("minecraft", |(namespace, _)| namespace))
+            .collect::<Vec<_>>();
+        self.known_packs
+            .iter()
+            .filter(|i| namespaces.contains(&i.namespace.as_str()))
+            .collect()
+    }
+}
+
+impl<T: Read + Write> MinecraftConnection<T> {
+    /// Send registry set to client and wait until it goes to play state, server side
+    ///
+    /// Must be called after login. Other packets of client, like Client Information, are skipped.
+    /// Returns packs that client has, fails if client doesn't have
+    /// [required packs](RegistrySet::required_packs)
+    pub fn configure_client(&mut self, set: &RegistrySet) -> Result<Vec<KnownPack>, ProtocolError> {
+        if self.protocol_version().is_some_and(|i| i < PROTOCOL_1_20_5) {
+            return Err(ProtocolError::ConfigurationError(
+                "protocol versions before 1.20.5 are not supported",
+            ));
+        }
+
+        self.send_versioned(&FeatureFlags {
+            features: set.feature_flags.clone(),
+        })?;
+        self.send_versioned(&ClientboundKnownPacks {
+            packs: set.known_packs.clone(),
+        })?;
+
+        let client_packs = loop {
+            match self.recv_any()? {
+                AnyPacket::ServerboundKnownPacks(packet) => break packet.packs,
+                AnyPacket::Unknown(_) => {}
+                _ => return Err(ProtocolError::ConfigurationError("unexpected packet")),
+            }
+        };
+
+        if set.required_packs().iter().any(|i| !client_packs.contains(i)) {
+            return Err(ProtocolError::ConfigurationError("client doesn't have required packs"));
+        }
+
+        for registry in &set.registries {
+            self.send_versioned(registry)?;
+        }
+        self.send_versioned(&UpdateTags {
+            registries: set.tags.clone(),
+        })?;
+        self.send_versioned(&FinishConfiguration)?;
+
+        loop {
+            match self.recv_any()? {
+                AnyPacket::AcknowledgeFinishConfiguration(_) => break,
+                AnyPacket::Unknown(_) => {}
+                _ => return Err(ProtocolError::ConfigurationError("unexpected packet")),
+            }
+        }
+
+        Ok(client_packs)
+    }
+}
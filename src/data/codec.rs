@@ -0,0 +1,58 @@
+//! `McRead` and `McWrite` traits for types that can be read and written as a whole
+
+use crate::{
+    data::{
+        nbt::Nbt,
+        types::{Angle, BitSet, Identifier, Position},
+    },
+    text::TextComponent,
+    DataReader, DataWriter, ProtocolError,
+};
+use uuid::Uuid;
+
+/// Type that can be read with [`DataReader`](DataReader)
+pub trait McRead: Sized {
+    /// Read value from reader
+    fn read_from<R: DataReader + ?Sized>(reader: &mut R) -> Result<Self, ProtocolError>;
+}
+
+/// Type that can be written with [`DataWriter`](DataWriter)
+pub trait McWrite {
+    /// Write value to writer
+    fn write_to<W: DataWriter + ?Sized>(&self, writer: &mut W) -> Result<(), ProtocolError>;
+}
+
+macro_rules! impl_codec {
+    ($type:ty, $reader:ident, $read:expr, $value:ident, $writer:ident, $write:expr) => {
+        impl McRead for $type {
+            fn read_from<R: DataReader + ?Sized>($reader: &mut R) -> Result<Self, ProtocolError> {
+                $read
+            }
+        }
+
+        impl McWrite for $type {
+            fn write_to<W: DataWriter + ?Sized>(&self, $writer: &mut W) -> Result<(), ProtocolError> {
+                let $value = self;
+                $write
+            }
+        }
+    };
+}
+
+impl_codec!(bool, r, r.read_boolean(), v, w, w.write_boolean(*v));
+impl_codec!(u8, r, r.read_byte(), v, w, w.write_byte(*v));
+impl_codec!(i8, r, Ok(r.read_byte()? as i8), v, w, w.write_byte(*v as u8));
+impl_codec!(u16, r, r.read_unsigned_short(), v, w, w.write_unsigned_short(*v));
+impl_codec!(i16, r, r.read_short(), v, w, w.write_short(*v));
+impl_codec!(i32, r, r.read_int(), v, w, w.write_int(*v));
+impl_codec!(i64, r, r.read_long(), v, w, w.write_long(*v));
+impl_codec!(f32, r, r.read_float(), v, w, w.write_float(*v));
+impl_codec!(f64, r, r.read_double(), v, w, w.write_double(*v));
+impl_codec!(String, r, r.read_string(), v, w, w.write_string(v));
+impl_codec!(Uuid, r, r.read_uuid(), v, w, w.write_uuid(v));
+impl_codec!(Nbt, r, r.read_nbt(), v, w, w.write_nbt(v));
+impl_codec!(TextComponent, r, r.read_text_component(), v, w, w.write_text_component(v));
+impl_codec!(Position, r, r.read_position(), v, w, w.write_position(v));
+impl_codec!(Angle, r, r.read_angle(), v, w, w.write_angle(*v));
+impl_codec!(BitSet, r, r.read_bitset(), v, w, w.write_bitset(v));
+impl_codec!(Identifier, r, r.read_identifier(), v, w, w.write_identifier(v));
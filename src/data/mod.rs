@@ -1,8 +1,13 @@
 //! `DataReader` and `DataWriter` traits for reading and writing primitive types in the Minecraft protocol
 
+pub mod codec;
+pub mod nbt;
 pub mod reader;
+pub mod types;
 pub mod varint;
 pub mod writer;
 
+pub use codec::*;
 pub use reader::*;
+pub use types::*;
 pub use writer::*;
@@ -0,0 +1,370 @@
+//! NBT tag tree, used for items, chunks, registries and `level.dat`-style files
+
+use crate::{limits::check_length, DataReader, DataWriter, ProtocolError};
+use flate2::{read::GzDecoder, read::ZlibDecoder, write::GzEncoder, Compression};
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{BufRead, BufReader, Read, Write},
+    path::Path,
+};
+
+/// Max depth of nested compounds and lists, same as vanilla
+pub const MAX_DEPTH: usize = 512;
+
+/// Bytes of byte array that are read at once, so declared length can't allocate
+/// more memory than there is data
+const BYTE_ARRAY_CHUNK: usize = 64 * 1024;
+
+/// NBT tag
+#[derive(Debug, Clone, PartialEq)]
+pub enum Nbt {
+    End,
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ByteArray(Vec<i8>),
+    String(String),
+    List(Vec<Nbt>),
+    Compound(BTreeMap<String, Nbt>),
+    IntArray(Vec<i32>),
+    LongArray(Vec<i64>),
+}
+
+impl Nbt {
+    /// Get tag type id
+    pub fn tag_id(&self) -> u8 {
+        match self {
+            Nbt::End => 0,
+            Nbt::Byte(_) => 1,
+            Nbt::Short(_) => 2,
+            Nbt::Int(_) => 3,
+            Nbt::Long(_) => 4,
+            Nbt::Float(_) => 5,
+            Nbt::Double(_) => 6,
+            Nbt::ByteArray(_) => 7,
+            Nbt::String(_) => 8,
+            Nbt::List(_) => 9,
+            Nbt::Compound(_) => 10,
+            Nbt::IntArray(_) => 11,
+            Nbt::LongArray(_) => 12,
+        }
+    }
+
+    /// Create empty compound
+    pub fn compound() -> Nbt {
+        Nbt::Compound(BTreeMap::new())
+    }
+
+    /// Get value from compound by key
+    pub fn get(&self, key: &str) -> Option<&Nbt> {
+        match self {
+            Nbt::Compound(map) => map.get(key),
+            _ => None,
+        }
+    }
+
+    /// Insert value into compound, does nothing if tag is not a compound
+    pub fn insert(&mut self, key: impl Into<String>, value: impl Into<Nbt>) {
+        if let Nbt::Compound(map) = self {
+            map.insert(key.into(), value.into());
+        }
+    }
+
+    /// Read uncompressed, gzip or zlib compressed named NBT, like `level.dat`
+    pub fn read_compressed<R: Read>(reader: R) -> Result<(String, Nbt), ProtocolError> {
+        let mut reader = BufReader::new(reader);
+        let magic = reader
+            .fill_buf()
+            .map_err(|e| ProtocolError::ReadError {
+                field: Some("NBT"),
+                source: e,
+            })?
+            .first()
+            .copied();
+
+        match magic {
+            Some(0x1f) => GzDecoder::new(reader).read_named_nbt(),
+            Some(0x78) => ZlibDecoder::new(reader).read_named_nbt(),
+            _ => reader.read_named_nbt(),
+        }
+    }
+
+    /// Write gzip compressed named NBT, like `level.dat`
+    pub fn write_compressed<W: Write>(
+        &self,
+        writer: W,
+        name: &str,
+    ) -> Result<(), ProtocolError> {
+        let mut encoder = GzEncoder::new(writer, Compression::default());
+        encoder.write_named_nbt(name, self)?;
+        encoder.finish().map_err(ProtocolError::ZlibError)?;
+        Ok(())
+    }
+
+    /// Read NBT file, see [`read_compressed`](Nbt::read_compressed)
+    pub fn read_file(path: impl AsRef<Path>) -> Result<(String, Nbt), ProtocolError> {
+        let file = File::open(path).map_err(|e| ProtocolError::ReadError {
+            field: Some("NBT file"),
+            source: e,
+        })?;
+        Nbt::read_compressed(file)
+    }
+
+    /// Write gzip compressed NBT file
+    pub fn write_file(&self, path: impl AsRef<Path>, name: &str) -> Result<(), ProtocolError> {
+        let file = File::create(path).map_err(ProtocolError::WriteError)?;
+        self.write_compressed(file, name)
+    }
+}
+
+macro_rules! impl_from {
+    ($type:ty, $variant:ident) => {
+        impl From<$type> for Nbt {
+            fn from(value: $type) -> Nbt {
+                Nbt::$variant(value.into())
+            }
+        }
+    };
+}
+
+impl_from!(i8, Byte);
+impl_from!(bool, Byte);
+impl_from!(i16, Short);
+impl_from!(i32, Int);
+impl_from!(i64, Long);
+impl_from!(f32, Float);
+impl_from!(f64, Double);
+impl_from!(String, String);
+impl_from!(&str, String);
+impl_from!(Vec<i8>, ByteArray);
+impl_from!(Vec<Nbt>, List);
+impl_from!(BTreeMap<String, Nbt>, Compound);
+impl_from!(Vec<i32>, IntArray);
+impl_from!(Vec<i64>, LongArray);
+
+/// Read NBT string in java modified UTF-8
+pub(crate) fn read_string<R: DataReader + ?Sized>(reader: &mut R) -> Result<String, ProtocolError> {
+    let size = reader.read_unsigned_short()? as usize;
+    let bytes = reader
+        .read_bytes(size)
+        .map_err(|e| e.with_field("NBT String"))?;
+    Ok(decode_mutf8(&bytes))
+}
+
+/// Write NBT string in java modified UTF-8
+pub(crate) fn write_string<W: DataWriter + ?Sized>(
+    writer: &mut W,
+    value: &str,
+) -> Result<(), ProtocolError> {
+    let bytes = encode_mutf8(value);
+    check_length("NBT String", bytes.len(), u16::MAX as usize)?;
+    writer.write_unsigned_short(bytes.len() as u16)?;
+    writer.write_bytes(&bytes)
+}
+
+fn read_length<R: DataReader + ?Sized>(reader: &mut R) -> Result<usize, ProtocolError> {
+    Ok(reader.read_int()?.max(0) as usize)
+}
+
+/// Read payload of tag with type `tag_id`
+pub(crate) fn read_payload<R: DataReader + ?Sized>(
+    reader: &mut R,
+    tag_id: u8,
+    depth: usize,
+) -> Result<Nbt, ProtocolError> {
+    if depth > MAX_DEPTH {
+        return Err(ProtocolError::NbtDepthError);
+    }
+
+    Ok(match tag_id {
+        0 => Nbt::End,
+        1 => Nbt::Byte(reader.read_byte()? as i8),
+        2 => Nbt::Short(reader.read_short()?),
+        3 => Nbt::Int(reader.read_int()?),
+        4 => Nbt::Long(reader.read_long()?),
+        5 => Nbt::Float(reader.read_float()?),
+        6 => Nbt::Double(reader.read_double()?),
+        7 => read_byte_array(reader)?,
+        8 => Nbt::String(read_string(reader)?),
+        9 => read_list(reader, depth)?,
+        10 => read_compound(reader, depth)?,
+        11 => read_int_array(reader)?,
+        12 => read_long_array(reader)?,
+        i => return Err(ProtocolError::NbtTagError(i)),
+    })
+}
+
+fn read_byte_array<R: DataReader + ?Sized>(reader: &mut R) -> Result<Nbt, ProtocolError> {
+    let size = read_length(reader)?;
+    let mut array = Vec::new();
+    while array.len() < size {
+        let chunk = (size - array.len()).min(BYTE_ARRAY_CHUNK);
+        let bytes = reader
+            .read_bytes(chunk)
+            .map_err(|e| e.with_field("NBT Byte Array"))?;
+        array.extend(bytes.into_iter().map(|i| i as i8));
+    }
+    Ok(Nbt::ByteArray(array))
+}
+
+fn read_list<R: DataReader + ?Sized>(reader: &mut R, depth: usize) -> Result<Nbt, ProtocolError> {
+    let element_id = reader.read_byte()?;
+    let size = read_length(reader)?;
+    if element_id == 0 && size != 0 {
+        return Err(ProtocolError::NbtListError);
+    }
+    let mut list = Vec::new();
+    for _ in 0..size {
+        list.push(read_payload(reader, element_id, depth + 1)?);
+    }
+    Ok(Nbt::List(list))
+}
+
+fn read_compound<R: DataReader + ?Sized>(
+    reader: &mut R,
+    depth: usize,
+) -> Result<Nbt, ProtocolError> {
+    let mut map = BTreeMap::new();
+    loop {
+        let element_id = reader.read_byte()?;
+        if element_id == 0 {
+            break;
+        }
+        let name = read_string(reader)?;
+        map.insert(name, read_payload(reader, element_id, depth + 1)?);
+    }
+    Ok(Nbt::Compound(map))
+}
+
+fn read_int_array<R: DataReader + ?Sized>(reader: &mut R) -> Result<Nbt, ProtocolError> {
+    let size = read_length(reader)?;
+    let mut array = Vec::new();
+    for _ in 0..size {
+        array.push(reader.read_int()?);
+    }
+    Ok(Nbt::IntArray(array))
+}
+
+fn read_long_array<R: DataReader + ?Sized>(reader: &mut R) -> Result<Nbt, ProtocolError> {
+    let size = read_length(reader)?;
+    let mut array = Vec::new();
+    for _ in 0..size {
+        array.push(reader.read_long()?);
+    }
+    Ok(Nbt::LongArray(array))
+}
+
+/// Write payload of tag
+pub(crate) fn write_payload<W: DataWriter + ?Sized>(
+    writer: &mut W,
+    nbt: &Nbt,
+) -> Result<(), ProtocolError> {
+    match nbt {
+        Nbt::End => Ok(()),
+        Nbt::Byte(i) => writer.write_byte(*i as u8),
+        Nbt::Short(i) => writer.write_short(*i),
+        Nbt::Int(i) => writer.write_int(*i),
+        Nbt::Long(i) => writer.write_long(*i),
+        Nbt::Float(i) => writer.write_float(*i),
+        Nbt::Double(i) => writer.write_double(*i),
+        Nbt::ByteArray(array) => {
+            writer.write_int(array.len() as i32)?;
+            writer.write_bytes(&array.iter().map(|i| *i as u8).collect::<Vec<u8>>())
+        }
+        Nbt::String(i) => write_string(writer, i),
+        Nbt::List(list) => {
+            let element_id = list.first().map(Nbt::tag_id).unwrap_or(0);
+            if list.iter().any(|i| i.tag_id() != element_id) {
+                return Err(ProtocolError::NbtListError);
+            }
+            if element_id == 0 && !list.is_empty() {
+                return Err(ProtocolError::NbtEndError);
+            }
+            writer.write_byte(element_id)?;
+            writer.write_int(list.len() as i32)?;
+            for element in list {
+                write_payload(writer, element)?;
+            }
+            Ok(())
+        }
+        Nbt::Compound(map) => {
+            for (name, element) in map {
+                if let Nbt::End = element {
+                    return Err(ProtocolError::NbtEndError);
+                }
+                writer.write_byte(element.tag_id())?;
+                write_string(writer, name)?;
+                write_payload(writer, element)?;
+            }
+            writer.write_byte(0)
+        }
+        Nbt::IntArray(array) => {
+            writer.write_int(array.len() as i32)?;
+            for i in array {
+                writer.write_int(*i)?;
+            }
+            Ok(())
+        }
+        Nbt::LongArray(array) => {
+            writer.write_int(array.len() as i32)?;
+            for i in array {
+                writer.write_long(*i)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Decode java modified UTF-8, unpaired surrogates are replaced
+fn decode_mutf8(bytes: &[u8]) -> String {
+    let mut units = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let a = bytes[i] as u16;
+        let (unit, size) = if a & 0x80 == 0 {
+            (a, 1)
+        } else if a & 0xe0 == 0xc0 && i + 1 < bytes.len() {
+            (((a & 0x1f) << 6) | (bytes[i + 1] as u16 & 0x3f), 2)
+        } else if a & 0xf0 == 0xe0 && i + 2 < bytes.len() {
+            (
+                ((a & 0x0f) << 12)
+                    | ((bytes[i + 1] as u16 & 0x3f) << 6)
+                    | (bytes[i + 2] as u16 & 0x3f),
+                3,
+            )
+        } else {
+            (0xfffd, 1)
+        };
+        units.push(unit);
+        i += size;
+    }
+
+    String::from_utf16_lossy(&units)
+}
+
+/// Encode java modified UTF-8
+fn encode_mutf8(value: &str) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(value.len());
+
+    for unit in value.encode_utf16() {
+        match unit {
+            0x0001..=0x007f => bytes.push(unit as u8),
+            0x0000 | 0x0080..=0x07ff => {
+                bytes.push(0xc0 | (unit >> 6) as u8);
+                bytes.push(0x80 | (unit & 0x3f) as u8);
+            }
+            _ => {
+                bytes.push(0xe0 | (unit >> 12) as u8);
+                bytes.push(0x80 | ((unit >> 6) & 0x3f) as u8);
+                bytes.push(0x80 | (unit & 0x3f) as u8);
+            }
+        }
+    }
+
+    bytes
+}
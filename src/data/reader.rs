@@ -1,11 +1,20 @@
 use crate::{
-    data::varint::{read_varint, size_varint},
+    data::{
+        nbt::{self, Nbt},
+        types::{Angle, BitSet, Identifier, Position},
+        varint::{read_varint, size_varint},
+    },
+    limits::{check_length, DEFAULT_MAX_STRING_LENGTH},
+    text::TextComponent,
     zigzag::Zigzag,
     ProtocolError,
 };
 use std::io::Read;
 use uuid::Uuid;
 
+/// Max element count of [`read_prefixed_array`](DataReader::read_prefixed_array)
+pub const DEFAULT_MAX_ARRAY_LENGTH: usize = 1 << 20;
+
 /// Packet data reader trait
 pub trait DataReader {
     /// Read bytes
@@ -13,68 +22,164 @@ pub trait DataReader {
 
     /// Read byte
     fn read_byte(&mut self) -> Result<u8, ProtocolError> {
-        Ok(self.read_bytes(1)?[0])
+        Ok(read_array::<1, _>(self, "Byte")?[0])
+    }
+    /// Max length of String in bytes, [`read_string`](Self::read_string) fails if it is longer
+    fn max_string_length(&self) -> usize {
+        DEFAULT_MAX_STRING_LENGTH
     }
     /// Read String
     fn read_string(&mut self) -> Result<String, ProtocolError> {
         let size = self.read_usize_varint()?;
-        match String::from_utf8(self.read_bytes(size)?) {
-            Ok(i) => Ok(i),
-            Err(_) => Err(ProtocolError::StringParseError),
-        }
+        check_length("String", size, self.max_string_length())?;
+        let bytes = self.read_bytes(size).map_err(|e| e.with_field("String"))?;
+        String::from_utf8(bytes).map_err(ProtocolError::StringParseError)
     }
     /// Read Unsigned Short as u16
     fn read_unsigned_short(&mut self) -> Result<u16, ProtocolError> {
-        match self.read_bytes(2)?.try_into() {
-            Ok(i) => Ok(u16::from_be_bytes(i)),
-            Err(_) => Err(ProtocolError::ReadError),
-        }
+        Ok(u16::from_be_bytes(read_array(self, "Unsigned Short")?))
     }
     /// Read Boolean
     fn read_boolean(&mut self) -> Result<bool, ProtocolError> {
-        Ok(self.read_byte()? == 0x01)
+        Ok(read_array::<1, _>(self, "Boolean")?[0] == 0x01)
     }
     /// Read Short as i16
     fn read_short(&mut self) -> Result<i16, ProtocolError> {
-        match self.read_bytes(2)?.try_into() {
-            Ok(i) => Ok(i16::from_be_bytes(i)),
-            Err(_) => Err(ProtocolError::ReadError),
-        }
+        Ok(i16::from_be_bytes(read_array(self, "Short")?))
     }
     /// Read Long as i64
     fn read_long(&mut self) -> Result<i64, ProtocolError> {
-        match self.read_bytes(8)?.try_into() {
-            Ok(i) => Ok(i64::from_be_bytes(i)),
-            Err(_) => Err(ProtocolError::ReadError),
-        }
+        Ok(i64::from_be_bytes(read_array(self, "Long")?))
     }
     /// Read Float as f32
     fn read_float(&mut self) -> Result<f32, ProtocolError> {
-        match self.read_bytes(4)?.try_into() {
-            Ok(i) => Ok(f32::from_be_bytes(i)),
-            Err(_) => Err(ProtocolError::ReadError),
-        }
+        Ok(f32::from_be_bytes(read_array(self, "Float")?))
     }
     /// Read Double as f64
     fn read_double(&mut self) -> Result<f64, ProtocolError> {
-        match self.read_bytes(8)?.try_into() {
-            Ok(i) => Ok(f64::from_be_bytes(i)),
-            Err(_) => Err(ProtocolError::ReadError),
-        }
+        Ok(f64::from_be_bytes(read_array(self, "Double")?))
     }
     /// Read Int as i32
     fn read_int(&mut self) -> Result<i32, ProtocolError> {
-        match self.read_bytes(4)?.try_into() {
-            Ok(i) => Ok(i32::from_be_bytes(i)),
-            Err(_) => Err(ProtocolError::ReadError),
-        }
+        Ok(i32::from_be_bytes(read_array(self, "Int")?))
     }
     /// Read UUID
     fn read_uuid(&mut self) -> Result<Uuid, ProtocolError> {
-        match self.read_bytes(16)?.try_into() {
-            Ok(i) => Ok(Uuid::from_bytes(i)),
-            Err(_) => Err(ProtocolError::ReadError),
+        Ok(Uuid::from_bytes(read_array(self, "UUID")?))
+    }
+
+    /// Read nameless NBT, used in network since 1.20.2
+    fn read_nbt(&mut self) -> Result<Nbt, ProtocolError> {
+        let tag_id = self.read_byte()?;
+        nbt::read_payload(self, tag_id, 0)
+    }
+    /// Read named NBT, used in files and in network before 1.20.2 (name, nbt)
+    fn read_named_nbt(&mut self) -> Result<(String, Nbt), ProtocolError> {
+        let tag_id = self.read_byte()?;
+        if tag_id == 0 {
+            return Ok((String::new(), Nbt::End));
         }
+        let name = nbt::read_string(self)?;
+        Ok((name, nbt::read_payload(self, tag_id, 0)?))
+    }
+    /// Read text component as NBT, used since 1.20.3
+    fn read_text_component(&mut self) -> Result<TextComponent, ProtocolError> {
+        TextComponent::from_nbt(&self.read_nbt()?)
+    }
+    /// Read text component as JSON string
+    fn read_json_text_component(&mut self) -> Result<TextComponent, ProtocolError> {
+        TextComponent::from_json(&self.read_string()?)
+    }
+    /// Read Position, packed in Long
+    fn read_position(&mut self) -> Result<Position, ProtocolError> {
+        Ok(Position::from_packed(self.read_long()?))
+    }
+    /// Read Angle
+    fn read_angle(&mut self) -> Result<Angle, ProtocolError> {
+        Ok(Angle(self.read_byte()?))
+    }
+    /// Read BitSet, VarInt count of longs and longs
+    fn read_bitset(&mut self) -> Result<BitSet, ProtocolError> {
+        Ok(BitSet::from_longs(self.read_prefixed_array(|r| r.read_long())?))
+    }
+    /// Read Fixed BitSet of `bits` bits
+    fn read_fixed_bitset(&mut self, bits: usize) -> Result<BitSet, ProtocolError> {
+        let bytes = self
+            .read_bytes(bits.div_ceil(8))
+            .map_err(|e| e.with_field("Fixed BitSet"))?;
+        Ok(BitSet::from_bytes(&bytes))
+    }
+    /// Read Identifier, fails if it has invalid characters
+    fn read_identifier(&mut self) -> Result<Identifier, ProtocolError> {
+        Identifier::parse(&self.read_string()?)
+    }
+
+    /// Read VarInt count and elements with `read`, count is limited by
+    /// [`DEFAULT_MAX_ARRAY_LENGTH`](DEFAULT_MAX_ARRAY_LENGTH)
+    fn read_prefixed_array<T>(
+        &mut self,
+        read: impl FnMut(&mut Self) -> Result<T, ProtocolError>,
+    ) -> Result<Vec<T>, ProtocolError> {
+        self.read_prefixed_array_max(DEFAULT_MAX_ARRAY_LENGTH, read)
+    }
+    /// Read VarInt count and elements with `read`,
+    /// fails before reading elements if count is more than `max`
+    fn read_prefixed_array_max<T>(
+        &mut self,
+        max: usize,
+        mut read: impl FnMut(&mut Self) -> Result<T, ProtocolError>,
+    ) -> Result<Vec<T>, ProtocolError> {
+        let length = self.read_usize_varint()?;
+        check_length("Prefixed Array", length, max)?;
+        let mut list = Vec::new();
+        for _ in 0..length {
+            list.push(read(self)?);
+        }
+        Ok(list)
+    }
+    /// Read Boolean and value with `read` if it is true
+    fn read_optional<T>(
+        &mut self,
+        read: impl FnOnce(&mut Self) -> Result<T, ProtocolError>,
+    ) -> Result<Option<T>, ProtocolError> {
+        if self.read_boolean()? {
+            Ok(Some(read(self)?))
+        } else {
+            Ok(None)
+        }
+    }
+    /// Read enum written as VarInt
+    fn read_varint_enum<E: TryFrom<i32, Error = ProtocolError>>(
+        &mut self,
+    ) -> Result<E, ProtocolError> {
+        E::try_from(self.read_varint()?)
+    }
+
+    /// Read VarInt as i32 with size in bytes (varint, size)
+    ///
+    /// Negative values are two's complement, like in vanilla (max 5 bytes)
+    fn read_varint_size(&mut self) -> Result<(i32, usize), ProtocolError> {
+        let (value, size) = self.read_u32_varint_size()?;
+        Ok((value as i32, size))
+    }
+    /// Read VarLong as i64 with size in bytes (varlong, size)
+    ///
+    /// Negative values are two's complement, like in vanilla (max 10 bytes)
+    fn read_varlong_size(&mut self) -> Result<(i64, usize), ProtocolError> {
+        let (value, size) = self.read_u64_varint_size()?;
+        Ok((value as i64, size))
+    }
+    /// Read VarInt as i32
+    ///
+    /// Negative values are two's complement, like in vanilla (max 5 bytes)
+    fn read_varint(&mut self) -> Result<i32, ProtocolError> {
+        Ok(self.read_u32_varint()? as i32)
+    }
+    /// Read VarLong as i64
+    ///
+    /// Negative values are two's complement, like in vanilla (max 10 bytes)
+    fn read_varlong(&mut self) -> Result<i64, ProtocolError> {
+        Ok(self.read_u64_varint()? as i64)
     }
 
     /// Read VarInt as usize with size in bytes (varint, size)
@@ -102,43 +207,43 @@ pub trait DataReader {
         size_varint!(u128, self)
     }
 
-    /// Read VarInt as isize with size in bytes (varint, size)
-    fn read_isize_varint_size(&mut self) -> Result<(isize, usize), ProtocolError> {
+    /// Read zigzag-encoded VarInt as isize with size in bytes (varint, size)
+    fn read_isize_zigzag_varint_size(&mut self) -> Result<(isize, usize), ProtocolError> {
         Ok({
             let i = self.read_usize_varint_size()?;
             (i.0.zigzag(), i.1)
         })
     }
-    /// Read VarInt as i8 with size in bytes (varint, size)
-    fn read_i8_varint_size(&mut self) -> Result<(i8, usize), ProtocolError> {
+    /// Read zigzag-encoded VarInt as i8 with size in bytes (varint, size)
+    fn read_i8_zigzag_varint_size(&mut self) -> Result<(i8, usize), ProtocolError> {
         Ok({
             let i = self.read_u8_varint_size()?;
             (i.0.zigzag(), i.1)
         })
     }
-    /// Read VarInt as i16 with size in bytes (varint, size)
-    fn read_i16_varint_size(&mut self) -> Result<(i16, usize), ProtocolError> {
+    /// Read zigzag-encoded VarInt as i16 with size in bytes (varint, size)
+    fn read_i16_zigzag_varint_size(&mut self) -> Result<(i16, usize), ProtocolError> {
         Ok({
             let i = self.read_u16_varint_size()?;
             (i.0.zigzag(), i.1)
         })
     }
-    /// Read VarInt as i32 with size in bytes (varint, size)
-    fn read_i32_varint_size(&mut self) -> Result<(i32, usize), ProtocolError> {
+    /// Read zigzag-encoded VarInt as i32 with size in bytes (varint, size)
+    fn read_i32_zigzag_varint_size(&mut self) -> Result<(i32, usize), ProtocolError> {
         Ok({
             let i = self.read_u32_varint_size()?;
             (i.0.zigzag(), i.1)
         })
     }
-    /// Read VarInt as i64 with size in bytes (varint, size)
-    fn read_i64_varint_size(&mut self) -> Result<(i64, usize), ProtocolError> {
+    /// Read zigzag-encoded VarInt as i64 with size in bytes (varint, size)
+    fn read_i64_zigzag_varint_size(&mut self) -> Result<(i64, usize), ProtocolError> {
         Ok({
             let i = self.read_u64_varint_size()?;
             (i.0.zigzag(), i.1)
         })
     }
-    /// Read VarInt as i128 with size in bytes (varint, size)
-    fn read_i128_varint_size(&mut self) -> Result<(i128, usize), ProtocolError> {
+    /// Read zigzag-encoded VarInt as i128 with size in bytes (varint, size)
+    fn read_i128_zigzag_varint_size(&mut self) -> Result<(i128, usize), ProtocolError> {
         Ok({
             let i = self.read_u128_varint_size()?;
             (i.0.zigzag(), i.1)
@@ -170,32 +275,47 @@ pub trait DataReader {
         read_varint!(u128, self)
     }
 
-    /// Read VarInt as isize
-    fn read_isize_varint(&mut self) -> Result<isize, ProtocolError> {
+    /// Read zigzag-encoded VarInt as isize
+    fn read_isize_zigzag_varint(&mut self) -> Result<isize, ProtocolError> {
         Ok(self.read_usize_varint()?.zigzag())
     }
-    /// Read VarInt as i8
-    fn read_i8_varint(&mut self) -> Result<i8, ProtocolError> {
+    /// Read zigzag-encoded VarInt as i8
+    fn read_i8_zigzag_varint(&mut self) -> Result<i8, ProtocolError> {
         Ok(self.read_u8_varint()?.zigzag())
     }
-    /// Read VarInt as i16
-    fn read_i16_varint(&mut self) -> Result<i16, ProtocolError> {
+    /// Read zigzag-encoded VarInt as i16
+    fn read_i16_zigzag_varint(&mut self) -> Result<i16, ProtocolError> {
         Ok(self.read_u16_varint()?.zigzag())
     }
-    /// Read VarInt as i32
-    fn read_i32_varint(&mut self) -> Result<i32, ProtocolError> {
+    /// Read zigzag-encoded VarInt as i32
+    fn read_i32_zigzag_varint(&mut self) -> Result<i32, ProtocolError> {
         Ok(self.read_u32_varint()?.zigzag())
     }
-    /// Read VarInt as i64
-    fn read_i64_varint(&mut self) -> Result<i64, ProtocolError> {
+    /// Read zigzag-encoded VarInt as i64
+    fn read_i64_zigzag_varint(&mut self) -> Result<i64, ProtocolError> {
         Ok(self.read_u64_varint()?.zigzag())
     }
-    /// Read VarInt as i128
-    fn read_i128_varint(&mut self) -> Result<i128, ProtocolError> {
+    /// Read zigzag-encoded VarInt as i128
+    fn read_i128_zigzag_varint(&mut self) -> Result<i128, ProtocolError> {
         Ok(self.read_u128_varint()?.zigzag())
     }
 }
 
+/// Read exact amount of bytes as array, `field` is type of value for errors
+pub(crate) fn read_array<const N: usize, R: DataReader + ?Sized>(
+    reader: &mut R,
+    field: &'static str,
+) -> Result<[u8; N], ProtocolError> {
+    let bytes = reader.read_bytes(N).map_err(|e| e.with_field(field))?;
+    let actual = bytes.len();
+    bytes.try_into().or(Err(ProtocolError::DataRanOutError {
+        field: Some(field),
+        packet_id: None,
+        expected: N,
+        actual,
+    }))
+}
+
 impl<R: Read> DataReader for R {
     fn read_bytes(&mut self, size: usize) -> Result<Vec<u8>, ProtocolError> {
         let mut buf = vec![0; size];
@@ -209,7 +329,7 @@ impl<R: Read> DataReader for R {
                 buf.append(&mut self.read_bytes(size-i)?);
                 Ok(buf)
             },
-            Err(_) => Err(ProtocolError::ReadError),
+            Err(e) => Err(ProtocolError::ReadError { field: None, source: e }),
         }
     }
 }
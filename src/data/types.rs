@@ -0,0 +1,218 @@
+//! Composite protocol types: Position, Angle, BitSet and Identifier
+
+use crate::ProtocolError;
+use std::{
+    fmt,
+    hash::{Hash, Hasher},
+};
+
+/// Block position, written as Long with packed 26 bit x, 26 bit z and 12 bit y
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Position {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+impl Position {
+    /// Create position
+    pub fn new(x: i32, y: i32, z: i32) -> Position {
+        Position { x, y, z }
+    }
+
+    /// Unpack position from Long
+    pub fn from_packed(value: i64) -> Position {
+        Position {
+            x: (value >> 38) as i32,
+            y: (value << 52 >> 52) as i32,
+            z: (value << 26 >> 38) as i32,
+        }
+    }
+
+    /// Pack position to Long, bits that don't fit are dropped
+    pub fn to_packed(&self) -> i64 {
+        ((self.x as i64 & 0x3FFFFFF) << 38)
+            | ((self.z as i64 & 0x3FFFFFF) << 12)
+            | (self.y as i64 & 0xFFF)
+    }
+}
+
+/// Rotation angle in steps of 1/256 of full turn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Angle(pub u8);
+
+impl Angle {
+    /// Create angle from degrees, rounded to nearest step
+    pub fn from_degrees(degrees: f32) -> Angle {
+        Angle((degrees / 360.0 * 256.0).round().rem_euclid(256.0) as u8)
+    }
+
+    /// Get angle in degrees, from 0 to 360
+    pub fn degrees(&self) -> f32 {
+        self.0 as f32 * 360.0 / 256.0
+    }
+}
+
+/// Set of bits, bit `i` is bit `i % 64` of long `i / 64`
+///
+/// Bitsets are equal if they have same set bits, trailing zero longs are ignored
+#[derive(Debug, Clone, Default)]
+pub struct BitSet {
+    words: Vec<u64>,
+}
+
+impl BitSet {
+    /// Create empty bitset
+    pub fn new() -> BitSet {
+        BitSet::default()
+    }
+
+    /// Create bitset from longs
+    pub fn from_longs(longs: Vec<i64>) -> BitSet {
+        BitSet {
+            words: longs.into_iter().map(|i| i as u64).collect(),
+        }
+    }
+
+    /// Get longs of bitset, trailing zero longs are removed
+    pub fn to_longs(&self) -> Vec<i64> {
+        self.trimmed_words().iter().map(|i| *i as i64).collect()
+    }
+
+    /// Words without trailing zero words
+    fn trimmed_words(&self) -> &[u64] {
+        let len = self.words.iter().rposition(|i| *i != 0).map_or(0, |i| i + 1);
+        &self.words[..len]
+    }
+
+    /// Get bit
+    pub fn get(&self, index: usize) -> bool {
+        self.words
+            .get(index / 64)
+            .is_some_and(|i| i & (1 << (index % 64)) != 0)
+    }
+
+    /// Set bit
+    pub fn set(&mut self, index: usize, value: bool) {
+        if index / 64 >= self.words.len() {
+            if !value {
+                return;
+            }
+            self.words.resize(index / 64 + 1, 0);
+        }
+        if value {
+            self.words[index / 64] |= 1 << (index % 64);
+        } else {
+            self.words[index / 64] &= !(1 << (index % 64));
+        }
+    }
+
+    /// Get index after highest set bit, 0 if bitset is empty
+    pub fn len(&self) -> usize {
+        self.words
+            .iter()
+            .rposition(|i| *i != 0)
+            .map_or(0, |i| i * 64 + 64 - self.words[i].leading_zeros() as usize)
+    }
+
+    /// Is there no set bits
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Create bitset from bytes of fixed bitset, bit `i` is bit `i % 8` of byte `i / 8`
+    pub fn from_bytes(bytes: &[u8]) -> BitSet {
+        let mut words = vec![0u64; bytes.len().div_ceil(8)];
+        for (i, byte) in bytes.iter().enumerate() {
+            words[i / 8] |= (*byte as u64) << (i % 8 * 8);
+        }
+        BitSet { words }
+    }
+
+    /// Get bytes of fixed bitset with `bits` bits, fails if higher bit is set
+    pub fn to_bytes(&self, bits: usize) -> Result<Vec<u8>, ProtocolError> {
+        let len = self.len();
+        if len > bits {
+            return Err(ProtocolError::InvalidValueError {
+                field: "Fixed BitSet",
+                value: len as i64 - 1,
+            });
+        }
+        Ok((0..bits.div_ceil(8))
+            .map(|i| {
+                self.words
+                    .get(i / 8)
+                    .map_or(0, |word| (word >> (i % 8 * 8)) as u8)
+            })
+            .collect())
+    }
+}
+
+impl PartialEq for BitSet {
+    fn eq(&self, other: &BitSet) -> bool {
+        self.trimmed_words() == other.trimmed_words()
+    }
+}
+
+impl Eq for BitSet {}
+
+impl Hash for BitSet {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.trimmed_words().hash(state);
+    }
+}
+
+/// Namespaced identifier, like `minecraft:stone`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Identifier {
+    namespace: String,
+    path: String,
+}
+
+impl Identifier {
+    /// Namespace of identifiers without namespace
+    pub const DEFAULT_NAMESPACE: &'static str = "minecraft";
+
+    /// Create identifier, fails if namespace or path has invalid characters
+    pub fn new(namespace: &str, path: &str) -> Result<Identifier, ProtocolError> {
+        let valid = |c: char, slash: bool| {
+            matches!(c, 'a'..='z' | '0'..='9' | '.' | '-' | '_') || (slash && c == '/')
+        };
+
+        if namespace.is_empty()
+            || !namespace.chars().all(|c| valid(c, false))
+            || !path.chars().all(|c| valid(c, true))
+        {
+            return Err(ProtocolError::IdentifierError(format!("{namespace}:{path}")));
+        }
+
+        Ok(Identifier {
+            namespace: namespace.to_string(),
+            path: path.to_string(),
+        })
+    }
+
+    /// Parse identifier, namespace is `minecraft` if it is not set
+    pub fn parse(value: &str) -> Result<Identifier, ProtocolError> {
+        match value.split_once(':') {
+            Some((namespace, path)) => Identifier::new(namespace, path),
+            None => Identifier::new(Identifier::DEFAULT_NAMESPACE, value),
+        }
+    }
+
+    /// Get namespace
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    /// Get path
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+impl fmt::Display for Identifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.namespace, self.path)
+    }
+}
@@ -5,11 +5,14 @@ macro_rules! size_varint {
         let mut size: usize = 0;
 
         loop {
-            let next = DataReader::read_byte($self)?;
+            let next = $crate::data::reader::read_array::<1, _>($self, "VarInt")?[0];
             size += 1;
 
             if shift >= (std::mem::size_of::<$type>() * 8) as $type {
-                return Err(ProtocolError::VarIntError);
+                return Err(ProtocolError::VarIntError {
+                    type_name: stringify!($type),
+                    max_size: (std::mem::size_of::<$type>() * 8).div_ceil(7),
+                });
             }
 
             decoded |= ((next & 0b01111111) as $type) << shift;
@@ -29,10 +32,13 @@ macro_rules! read_varint {
         let mut decoded: $type = 0;
 
         loop {
-            let next = DataReader::read_byte($self)?;
+            let next = $crate::data::reader::read_array::<1, _>($self, "VarInt")?[0];
 
             if shift >= (std::mem::size_of::<$type>() * 8) as $type {
-                return Err(ProtocolError::VarIntError);
+                return Err(ProtocolError::VarIntError {
+                    type_name: stringify!($type),
+                    max_size: (std::mem::size_of::<$type>() * 8).div_ceil(7),
+                });
             }
 
             decoded |= ((next & 0b01111111) as $type) << shift;
@@ -1,4 +1,13 @@
-use crate::{data::varint::write_varint, zigzag::Zigzag, ProtocolError};
+use crate::{
+    data::{
+        nbt::{self, Nbt},
+        types::{Angle, BitSet, Identifier, Position},
+        varint::write_varint,
+    },
+    text::TextComponent,
+    zigzag::Zigzag,
+    ProtocolError,
+};
 use std::io::Write;
 use uuid::Uuid;
 
@@ -23,53 +32,119 @@ pub trait DataWriter {
     }
     /// Write Unsigned Short as u16
     fn write_unsigned_short(&mut self, val: u16) -> Result<(), ProtocolError> {
-        match self.write_bytes(&val.to_be_bytes()) {
-            Ok(_) => Ok(()),
-            Err(_) => Err(ProtocolError::UnsignedShortError),
-        }
+        self.write_bytes(&val.to_be_bytes())
     }
     /// Write Boolean
     fn write_boolean(&mut self, val: bool) -> Result<(), ProtocolError> {
-        match self.write_byte(if val { 0x01 } else { 0x00 }) {
-            Ok(_) => Ok(()),
-            Err(_) => Err(ProtocolError::UnsignedShortError),
-        }
+        self.write_byte(if val { 0x01 } else { 0x00 })
     }
     /// Write Short as i16
     fn write_short(&mut self, val: i16) -> Result<(), ProtocolError> {
-        match self.write_bytes(&val.to_be_bytes()) {
-            Ok(_) => Ok(()),
-            Err(_) => Err(ProtocolError::UnsignedShortError),
-        }
+        self.write_bytes(&val.to_be_bytes())
     }
     /// Write Long as i64
     fn write_long(&mut self, val: i64) -> Result<(), ProtocolError> {
-        match self.write_bytes(&val.to_be_bytes()) {
-            Ok(_) => Ok(()),
-            Err(_) => Err(ProtocolError::UnsignedShortError),
-        }
+        self.write_bytes(&val.to_be_bytes())
     }
     /// Write Float as f32
     fn write_float(&mut self, val: f32) -> Result<(), ProtocolError> {
-        match self.write_bytes(&val.to_be_bytes()) {
-            Ok(_) => Ok(()),
-            Err(_) => Err(ProtocolError::UnsignedShortError),
-        }
+        self.write_bytes(&val.to_be_bytes())
     }
     /// Write Double as f64
     fn write_double(&mut self, val: f64) -> Result<(), ProtocolError> {
-        match self.write_bytes(&val.to_be_bytes()) {
-            Ok(_) => Ok(()),
-            Err(_) => Err(ProtocolError::UnsignedShortError),
-        }
+        self.write_bytes(&val.to_be_bytes())
     }
     /// Write Int as i32
     fn write_int(&mut self, val: i32) -> Result<(), ProtocolError> {
-        match self.write_bytes(&val.to_be_bytes()) {
-            Ok(_) => Ok(()),
-            Err(_) => Err(ProtocolError::UnsignedShortError),
+        self.write_bytes(&val.to_be_bytes())
+    }
+
+    /// Write nameless NBT, used in network since 1.20.2
+    fn write_nbt(&mut self, val: &Nbt) -> Result<(), ProtocolError> {
+        self.write_byte(val.tag_id())?;
+        nbt::write_payload(self, val)
+    }
+    /// Write named NBT, used in files and in network before 1.20.2
+    ///
+    /// Fails with [`NbtEndError`](ProtocolError::NbtEndError) if `val` is End tag
+    fn write_named_nbt(&mut self, name: &str, val: &Nbt) -> Result<(), ProtocolError> {
+        if let Nbt::End = val {
+            return Err(ProtocolError::NbtEndError);
+        }
+        self.write_byte(val.tag_id())?;
+        nbt::write_string(self, name)?;
+        nbt::write_payload(self, val)
+    }
+    /// Write text component as NBT, used since 1.20.3
+    fn write_text_component(&mut self, val: &TextComponent) -> Result<(), ProtocolError> {
+        self.write_nbt(&val.to_nbt())
+    }
+    /// Write text component as JSON string
+    fn write_json_text_component(&mut self, val: &TextComponent) -> Result<(), ProtocolError> {
+        self.write_string(&val.to_json())
+    }
+    /// Write Position, packed in Long
+    fn write_position(&mut self, val: &Position) -> Result<(), ProtocolError> {
+        self.write_long(val.to_packed())
+    }
+    /// Write Angle
+    fn write_angle(&mut self, val: Angle) -> Result<(), ProtocolError> {
+        self.write_byte(val.0)
+    }
+    /// Write BitSet, VarInt count of longs and longs
+    fn write_bitset(&mut self, val: &BitSet) -> Result<(), ProtocolError> {
+        self.write_prefixed_array(&val.to_longs(), |w, long| w.write_long(*long))
+    }
+    /// Write Fixed BitSet of `bits` bits, fails if higher bit is set
+    fn write_fixed_bitset(&mut self, val: &BitSet, bits: usize) -> Result<(), ProtocolError> {
+        self.write_bytes(&val.to_bytes(bits)?)
+    }
+    /// Write Identifier
+    fn write_identifier(&mut self, val: &Identifier) -> Result<(), ProtocolError> {
+        self.write_string(&val.to_string())
+    }
+
+    /// Write VarInt count and elements with `write`
+    fn write_prefixed_array<T>(
+        &mut self,
+        list: &[T],
+        mut write: impl FnMut(&mut Self, &T) -> Result<(), ProtocolError>,
+    ) -> Result<(), ProtocolError> {
+        self.write_usize_varint(list.len())?;
+        for value in list {
+            write(self, value)?;
+        }
+        Ok(())
+    }
+    /// Write Boolean and value with `write` if it is present
+    fn write_optional<T>(
+        &mut self,
+        value: Option<&T>,
+        write: impl FnOnce(&mut Self, &T) -> Result<(), ProtocolError>,
+    ) -> Result<(), ProtocolError> {
+        self.write_boolean(value.is_some())?;
+        match value {
+            Some(value) => write(self, value),
+            None => Ok(()),
         }
     }
+    /// Write enum as VarInt
+    fn write_varint_enum<E: Into<i32>>(&mut self, val: E) -> Result<(), ProtocolError> {
+        self.write_varint(val.into())
+    }
+
+    /// Write VarInt as i32
+    ///
+    /// Negative values are two's complement, like in vanilla (always 5 bytes)
+    fn write_varint(&mut self, val: i32) -> Result<(), ProtocolError> {
+        self.write_u32_varint(val as u32)
+    }
+    /// Write VarLong as i64
+    ///
+    /// Negative values are two's complement, like in vanilla (always 10 bytes)
+    fn write_varlong(&mut self, val: i64) -> Result<(), ProtocolError> {
+        self.write_u64_varint(val as u64)
+    }
 
     /// Write VarInt as usize
     fn write_usize_varint(&mut self, val: usize) -> Result<(), ProtocolError> {
@@ -96,28 +171,28 @@ pub trait DataWriter {
         write_varint!(u128, self, val)
     }
 
-    /// Write VarInt as isize
-    fn write_isize_varint(&mut self, val: isize) -> Result<(), ProtocolError> {
+    /// Write zigzag-encoded VarInt as isize
+    fn write_isize_zigzag_varint(&mut self, val: isize) -> Result<(), ProtocolError> {
         self.write_usize_varint(val.zigzag())
     }
-    /// Write VarInt as i8
-    fn write_i8_varint(&mut self, val: i8) -> Result<(), ProtocolError> {
+    /// Write zigzag-encoded VarInt as i8
+    fn write_i8_zigzag_varint(&mut self, val: i8) -> Result<(), ProtocolError> {
         self.write_u8_varint(val.zigzag())
     }
-    /// Write VarInt as i16
-    fn write_i16_varint(&mut self, val: i16) -> Result<(), ProtocolError> {
+    /// Write zigzag-encoded VarInt as i16
+    fn write_i16_zigzag_varint(&mut self, val: i16) -> Result<(), ProtocolError> {
         self.write_u16_varint(val.zigzag())
     }
-    /// Write VarInt as i32
-    fn write_i32_varint(&mut self, val: i32) -> Result<(), ProtocolError> {
+    /// Write zigzag-encoded VarInt as i32
+    fn write_i32_zigzag_varint(&mut self, val: i32) -> Result<(), ProtocolError> {
         self.write_u32_varint(val.zigzag())
     }
-    /// Write VarInt as i64
-    fn write_i64_varint(&mut self, val: i64) -> Result<(), ProtocolError> {
+    /// Write zigzag-encoded VarInt as i64
+    fn write_i64_zigzag_varint(&mut self, val: i64) -> Result<(), ProtocolError> {
         self.write_u64_varint(val.zigzag())
     }
-    /// Write VarInt as i128
-    fn write_i128_varint(&mut self, val: i128) -> Result<(), ProtocolError> {
+    /// Write zigzag-encoded VarInt as i128
+    fn write_i128_zigzag_varint(&mut self, val: i128) -> Result<(), ProtocolError> {
         self.write_u128_varint(val.zigzag())
     }
 }
@@ -126,7 +201,7 @@ impl<W: Write> DataWriter for W {
     fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), ProtocolError> {
         match self.write_all(bytes) {
             Ok(_) => Ok(()),
-            Err(_) => Err(ProtocolError::WriteError),
+            Err(e) => Err(ProtocolError::WriteError(e)),
         }
     }
 }
\ No newline at end of file
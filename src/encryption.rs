@@ -0,0 +1,120 @@
+//! AES-128-CFB8 protocol encryption
+
+use crate::ProtocolError;
+use aes::{
+    cipher::{generic_array::GenericArray, inout::InOutBuf, BlockDecryptMut, BlockEncryptMut, KeyIvInit},
+    Aes128,
+};
+use std::io::Write;
+
+#[cfg(feature = "atomic_clone")]
+use std::sync::{Arc, Mutex};
+
+/// AES-128-CFB8 encryptor
+pub type Encryptor = cfb8::Encryptor<Aes128>;
+
+/// AES-128-CFB8 decryptor
+pub type Decryptor = cfb8::Decryptor<Aes128>;
+
+/// Cipher shared between clones of connection
+#[cfg(feature = "atomic_clone")]
+pub(crate) type CipherCell<C> = Arc<Mutex<Option<C>>>;
+
+/// Cipher owned by connection
+#[cfg(not(feature = "atomic_clone"))]
+pub(crate) type CipherCell<C> = Option<C>;
+
+/// Create encryptor and decryptor from shared secret
+///
+/// Minecraft uses shared secret as both key and iv, so it must be 16 bytes long
+pub fn new_cipher(shared_secret: &[u8]) -> Result<(Encryptor, Decryptor), ProtocolError> {
+    if shared_secret.len() != 16 {
+        return Err(ProtocolError::EncryptionError {
+            expected: 16,
+            actual: shared_secret.len(),
+        });
+    }
+
+    let key = GenericArray::from_slice(shared_secret);
+    Ok((Encryptor::new(key, key), Decryptor::new(key, key)))
+}
+
+/// Encrypt bytes in place
+pub fn encrypt(encryptor: &mut Encryptor, data: &mut [u8]) {
+    let (blocks, _) = InOutBuf::from(data).into_chunks();
+    encryptor.encrypt_blocks_inout_mut(blocks);
+}
+
+/// Decrypt bytes in place
+pub fn decrypt(decryptor: &mut Decryptor, data: &mut [u8]) {
+    let (blocks, _) = InOutBuf::from(data).into_chunks();
+    decryptor.decrypt_blocks_inout_mut(blocks);
+}
+
+pub(crate) fn new_cell<C>() -> CipherCell<C> {
+    #[cfg(feature = "atomic_clone")]
+    {
+        Arc::new(Mutex::new(None))
+    }
+    #[cfg(not(feature = "atomic_clone"))]
+    {
+        None
+    }
+}
+
+pub(crate) fn set_cell<C>(cell: &mut CipherCell<C>, cipher: C) {
+    #[cfg(feature = "atomic_clone")]
+    {
+        *cell.lock().unwrap() = Some(cipher);
+    }
+    #[cfg(not(feature = "atomic_clone"))]
+    {
+        *cell = Some(cipher);
+    }
+}
+
+pub(crate) fn is_cell_set<C>(cell: &CipherCell<C>) -> bool {
+    #[cfg(feature = "atomic_clone")]
+    {
+        cell.lock().unwrap().is_some()
+    }
+    #[cfg(not(feature = "atomic_clone"))]
+    {
+        cell.is_some()
+    }
+}
+
+/// Decrypt bytes in place if decryptor is set
+pub(crate) fn decrypt_cell(cell: &mut CipherCell<Decryptor>, data: &mut [u8]) {
+    #[cfg(feature = "atomic_clone")]
+    let mut decryptor = cell.lock().unwrap();
+    #[cfg(not(feature = "atomic_clone"))]
+    let decryptor = cell;
+
+    if let Some(decryptor) = decryptor.as_mut() {
+        decrypt(decryptor, data);
+    }
+}
+
+/// Encrypt bytes in place if encryptor is set and write them to stream
+pub(crate) fn write_encrypted<T: Write>(
+    stream: &mut T,
+    cell: &mut CipherCell<Encryptor>,
+    data: &mut [u8],
+) -> Result<(), ProtocolError> {
+    // lock is held until bytes are written, so the order
+    // of encrypted bytes in the stream matches the cipher state
+    #[cfg(feature = "atomic_clone")]
+    let mut encryptor = cell.lock().unwrap();
+    #[cfg(not(feature = "atomic_clone"))]
+    let encryptor = cell;
+
+    if let Some(encryptor) = encryptor.as_mut() {
+        encrypt(encryptor, data);
+    }
+
+    stream
+        .write_all(data)
+        .and_then(|_| stream.flush())
+        .map_err(ProtocolError::WriteError)
+}
@@ -1,96 +1,335 @@
 #[cfg(test)]
+#[allow(clippy::excessive_precision, clippy::bool_assert_comparison, clippy::useless_vec)]
 mod tests;
 
+// lets derive macros refer to `::rust_mc_proto` from inside the crate
+extern crate self as rust_mc_proto;
+
+#[cfg(feature = "tokio")]
+pub mod asynchronous;
+mod buffer;
+pub mod compression;
+pub mod configuration;
 pub mod data;
+pub mod encryption;
+pub mod limits;
+pub mod login;
 pub mod packet;
+pub mod packets;
+pub mod registry;
+pub mod server;
+pub mod state;
+pub mod status;
+pub mod text;
 pub mod zigzag;
 
 pub mod prelude {
-    pub use crate::{DataReader, DataWriter};
+    pub use crate::{packets::McPacket, DataReader, DataWriter, McRead, McWrite};
 }
 
 pub use crate::{
-    data::{DataReader, DataWriter},
-    packet::Packet,
+    data::{DataReader, DataWriter, McRead, McWrite},
+    limits::Limits,
+    packet::{Packet, PacketData},
+    packets::McPacket,
+    registry::{ProtocolRegistry, RegistryPacket},
+    state::{ConnectionState, Direction, Side},
+    text::TextComponent,
 };
 
-use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
+#[cfg(feature = "tokio")]
+pub use crate::asynchronous::{AsyncMCConn, AsyncMCConnTcp, AsyncMinecraftConnection};
+
+#[cfg(feature = "derive")]
+pub use rust_mc_proto_derive::{McPacket, McRead, McWrite};
+
+use crate::limits::check_length;
+use crate::buffer::BufferCell;
+use crate::compression::{Compressor, Decompressor};
+use crate::encryption::{CipherCell, Decryptor, Encryptor};
+use crate::packet::PacketBuffer;
+use crate::packets::AnyPacket;
+use crate::state::TrackerCell;
+
 use std::{
-    error::Error, fmt, io::{Read, Write}, net::{TcpStream, ToSocketAddrs}, sync::atomic::AtomicBool, usize
+    error::Error, fmt, io::{self, Cursor, Read, Write}, net::{SocketAddr, TcpStream, ToSocketAddrs},
+    string::FromUtf8Error, sync::Arc, time::Duration,
 };
 
 #[cfg(feature = "atomic_clone")]
-use std::sync::{
-    atomic::{AtomicUsize, Ordering},
-    Arc,
-};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
 /// Minecraft protocol error
 #[derive(Debug)]
 pub enum ProtocolError {
-    AddressParseError,
-    DataRanOutError,
-    StringParseError,
-    StreamConnectError,
-    VarIntError,
-    ReadError,
-    WriteError,
-    ZlibError,
-    UnsignedShortError,
-    CloneError,
-    ConnectionClosedError
+    /// Address can't be resolved, `None` if it resolved to nothing
+    AddressParseError(Option<io::Error>),
+    /// Not enough data to read value
+    DataRanOutError {
+        /// Type of value that was read
+        field: Option<&'static str>,
+        /// Id of packet that was read
+        packet_id: Option<i32>,
+        /// Bytes required
+        expected: usize,
+        /// Bytes available
+        actual: usize,
+    },
+    /// String is not valid UTF-8
+    StringParseError(FromUtf8Error),
+    /// Can't connect to address
+    StreamConnectError(io::Error),
+    /// VarInt doesn't fit into type
+    VarIntError {
+        /// Type that VarInt was read as
+        type_name: &'static str,
+        /// Max size of VarInt in bytes
+        max_size: usize,
+    },
+    /// Can't read from stream
+    ReadError {
+        /// Type of value that was read
+        field: Option<&'static str>,
+        source: io::Error,
+    },
+    /// Can't write to stream
+    WriteError(io::Error),
+    /// Zlib compression or decompression failed
+    ZlibError(io::Error),
+    /// Can't clone stream
+    CloneError(io::Error),
+    /// Connection is closed
+    ConnectionClosedError,
+    /// Unknown NBT tag type
+    NbtTagError(u8),
+    /// NBT is nested deeper than [`MAX_DEPTH`](data::nbt::MAX_DEPTH)
+    NbtDepthError,
+    /// NBT list contains tags of different types
+    NbtListError,
+    /// NBT End tag is written as compound value, list element or named root
+    NbtEndError,
+    /// Packet has other id than expected
+    UnexpectedPacketError {
+        expected: i32,
+        actual: i32,
+    },
+    /// Value is not valid for field
+    InvalidValueError {
+        field: &'static str,
+        value: i64,
+    },
+    /// Shared secret has invalid length
+    EncryptionError {
+        /// Required length of shared secret
+        expected: usize,
+        /// Length of given shared secret
+        actual: usize,
+    },
+    /// Line of registry data is invalid
+    RegistryParseError {
+        line: usize,
+    },
+    /// Packet has no id in protocol version
+    UnsupportedPacketError {
+        protocol: i32,
+        name: &'static str,
+    },
+    /// JSON is not valid
+    JsonError(serde_json::Error),
+    /// Text component is not valid, contains name of invalid field or problem
+    TextComponentError(&'static str),
+    /// Status response is not valid, contains name of invalid field
+    StatusError(&'static str),
+    /// Disconnected by other side, contains reason
+    DisconnectError(Box<TextComponent>),
+    /// Login can't be done, contains problem
+    LoginError(&'static str),
+    /// Configuration can't be done, contains problem
+    ConfigurationError(&'static str),
+    /// Identifier has invalid characters, contains identifier
+    IdentifierError(String),
+    /// Length of value is more than limit
+    LengthLimitError {
+        /// Type of value that was read
+        field: &'static str,
+        length: usize,
+        max: usize,
+    },
+    /// Declared Data Length of compressed packet is not same as decompressed length,
+    /// only in [`strict`](Limits::strict) mode
+    DataLengthError {
+        /// Declared length
+        expected: usize,
+        /// Decompressed length
+        actual: usize,
+    },
+    /// Packet is compressed while shorter than threshold or is not compressed while
+    /// not shorter than threshold, only in [`strict`](Limits::strict) mode
+    CompressionThresholdError {
+        length: usize,
+        threshold: usize,
+        /// Whether packet was compressed
+        compressed: bool,
+    },
+    /// Typed packet has bytes after body, only in [`strict`](Limits::strict) mode
+    TrailingBytesError {
+        packet_id: i32,
+        /// Count of bytes after body
+        count: usize,
+    },
+}
+
+impl ProtocolError {
+    /// Set type of value that was read, if error doesn't have one yet
+    pub fn with_field(mut self, name: &'static str) -> ProtocolError {
+        match &mut self {
+            ProtocolError::DataRanOutError { field, .. } | ProtocolError::ReadError { field, .. } => {
+                field.get_or_insert(name);
+            }
+            _ => {}
+        }
+        self
+    }
+
+    /// Set id of packet that was read, if error doesn't have one yet
+    pub fn with_packet_id(mut self, id: i32) -> ProtocolError {
+        if let ProtocolError::DataRanOutError { packet_id, .. } = &mut self {
+            packet_id.get_or_insert(id);
+        }
+        self
+    }
 }
 
 impl fmt::Display for ProtocolError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "An protocol error occured")
+        match self {
+            ProtocolError::AddressParseError(Some(e)) => write!(f, "can't resolve address: {e}"),
+            ProtocolError::AddressParseError(None) => write!(f, "address resolved to nothing"),
+            ProtocolError::DataRanOutError { field, packet_id, expected, actual } => {
+                write!(f, "data ran out")?;
+                if let Some(field) = field {
+                    write!(f, " while reading {field}")?;
+                }
+                if let Some(packet_id) = packet_id {
+                    write!(f, " in packet 0x{packet_id:02X}")?;
+                }
+                write!(f, ": expected {expected} bytes, got {actual}")
+            }
+            ProtocolError::StringParseError(e) => write!(f, "string is not valid UTF-8: {e}"),
+            ProtocolError::StreamConnectError(e) => write!(f, "can't connect: {e}"),
+            ProtocolError::VarIntError { type_name, max_size } => {
+                write!(f, "VarInt is too big for {type_name} (max {max_size} bytes)")
+            }
+            ProtocolError::ReadError { field: Some(field), source } => {
+                write!(f, "can't read {field}: {source}")
+            }
+            ProtocolError::ReadError { field: None, source } => write!(f, "can't read: {source}"),
+            ProtocolError::WriteError(e) => write!(f, "can't write: {e}"),
+            ProtocolError::ZlibError(e) => write!(f, "zlib error: {e}"),
+            ProtocolError::CloneError(e) => write!(f, "can't clone stream: {e}"),
+            ProtocolError::ConnectionClosedError => write!(f, "connection closed"),
+            ProtocolError::NbtTagError(i) => write!(f, "unknown NBT tag type {i}"),
+            ProtocolError::NbtDepthError => {
+                write!(f, "NBT is nested deeper than {}", data::nbt::MAX_DEPTH)
+            }
+            ProtocolError::NbtListError => write!(f, "NBT list contains tags of different types"),
+            ProtocolError::NbtEndError => write!(f, "NBT End tag can't be written as value"),
+            ProtocolError::UnexpectedPacketError { expected, actual } => {
+                write!(f, "expected packet 0x{expected:02X}, got 0x{actual:02X}")
+            }
+            ProtocolError::InvalidValueError { field, value } => {
+                write!(f, "invalid value {value} for {field}")
+            }
+            ProtocolError::EncryptionError { expected, actual } => {
+                write!(f, "shared secret must be {expected} bytes long, got {actual}")
+            }
+            ProtocolError::RegistryParseError { line } => {
+                write!(f, "invalid registry entry on line {line}")
+            }
+            ProtocolError::UnsupportedPacketError { protocol, name } => {
+                write!(f, "packet {name} is not supported by protocol {protocol}")
+            }
+            ProtocolError::JsonError(e) => write!(f, "invalid JSON: {e}"),
+            ProtocolError::TextComponentError(e) => write!(f, "invalid text component: {e}"),
+            ProtocolError::StatusError(e) => write!(f, "invalid status response: {e}"),
+            ProtocolError::DisconnectError(reason) => write!(f, "disconnected: {reason}"),
+            ProtocolError::LoginError(e) => write!(f, "login failed: {e}"),
+            ProtocolError::ConfigurationError(e) => write!(f, "configuration failed: {e}"),
+            ProtocolError::IdentifierError(e) => write!(f, "invalid identifier: {e}"),
+            ProtocolError::LengthLimitError { field, length, max } => {
+                write!(f, "{field} length {length} is more than {max}")
+            }
+            ProtocolError::DataLengthError { expected, actual } => {
+                write!(f, "decompressed packet length {actual} is not declared length {expected}")
+            }
+            ProtocolError::CompressionThresholdError { length, threshold, compressed: true } => {
+                write!(f, "compressed packet length {length} is below threshold {threshold}")
+            }
+            ProtocolError::CompressionThresholdError { length, threshold, compressed: false } => {
+                write!(f, "uncompressed packet length {length} is not below threshold {threshold}")
+            }
+            ProtocolError::TrailingBytesError { packet_id, count } => {
+                write!(f, "packet 0x{packet_id:02X} has {count} bytes after body")
+            }
+        }
     }
 }
 
-impl Error for ProtocolError {}
+impl Error for ProtocolError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ProtocolError::AddressParseError(Some(e))
+            | ProtocolError::StreamConnectError(e)
+            | ProtocolError::ReadError { source: e, .. }
+            | ProtocolError::WriteError(e)
+            | ProtocolError::ZlibError(e)
+            | ProtocolError::CloneError(e) => Some(e),
+            ProtocolError::StringParseError(e) => Some(e),
+            ProtocolError::JsonError(e) => Some(e),
+            _ => None,
+        }
+    }
+}
 
-/// Minecraft connection, wrapper for stream with compression
+/// Minecraft connection, wrapper for stream with compression and encryption
 pub struct MinecraftConnection<T: Read + Write> {
     stream: T,
+    encryptor: CipherCell<Encryptor>,
+    decryptor: CipherCell<Decryptor>,
     #[cfg(feature = "atomic_clone")]
     compression: Arc<AtomicUsize>,
     #[cfg(not(feature = "atomic_clone"))]
     compression: Option<usize>,
-    compression_type: u32,
+    compressor: Compressor,
+    decompressor: Decompressor,
     #[cfg(feature = "atomic_clone")]
     is_alive: Arc<AtomicBool>,
     #[cfg(not(feature = "atomic_clone"))]
     is_alive: bool,
+    tracker: TrackerCell,
+    limits: Limits,
+    read_buffer: BufferCell,
+    write_buffer: Vec<u8>,
 }
 
 impl MinecraftConnection<TcpStream> {
     /// Connect to Minecraft Server with TcpStream
     pub fn connect(addr: &str) -> Result<MinecraftConnection<TcpStream>, ProtocolError> {
-        let addr = match addr.to_socket_addrs() {
-            Ok(mut i) => match i.next() {
-                Some(i) => i,
-                None => return Err(ProtocolError::AddressParseError),
-            },
-            Err(_) => return Err(ProtocolError::AddressParseError),
-        };
-
-        let stream: TcpStream = match TcpStream::connect(&addr) {
-            Ok(i) => i,
-            Err(_) => return Err(ProtocolError::StreamConnectError),
-        };
+        match TcpStream::connect(resolve_addr(addr)?) {
+            Ok(stream) => Ok(MinecraftConnection::new(stream)),
+            Err(e) => Err(ProtocolError::StreamConnectError(e)),
+        }
+    }
 
-        Ok(MinecraftConnection {
-            stream,
-            #[cfg(feature = "atomic_clone")]
-            compression: Arc::new(AtomicUsize::new(usize::MAX)),
-            #[cfg(not(feature = "atomic_clone"))]
-            compression: None,
-            #[cfg(feature = "atomic_clone")]
-            is_alive: Arc::new(AtomicBool::new(true)),
-            #[cfg(not(feature = "atomic_clone"))]
-            is_alive: true,
-            compression_type: 1,
-        })
+    /// Connect to Minecraft Server with TcpStream, fails if connecting takes longer than `timeout`
+    pub fn connect_timeout(
+        addr: &str,
+        timeout: Duration,
+    ) -> Result<MinecraftConnection<TcpStream>, ProtocolError> {
+        match TcpStream::connect_timeout(&resolve_addr(addr)?, timeout) {
+            Ok(stream) => Ok(MinecraftConnection::new(stream)),
+            Err(e) => Err(ProtocolError::StreamConnectError(e)),
+        }
     }
 
     /// Close TcpStream
@@ -107,36 +346,46 @@ impl MinecraftConnection<TcpStream> {
         self.is_alive.store(false, Ordering::Relaxed);
     }
 
-    /// Try clone MinecraftConnection with compression and stream
+    /// Try clone MinecraftConnection with compression, encryption and stream
+    ///
+    /// Without `atomic_clone` feature clone starts with empty read buffer,
+    /// bytes that are already read by this connection are not seen by clone
     pub fn try_clone(&self) -> Result<MinecraftConnection<TcpStream>, ProtocolError> {
         match self.stream.try_clone() {
             Ok(stream) => Ok(MinecraftConnection {
                 stream,
                 is_alive: self.is_alive.clone(),
                 compression: self.compression.clone(),
-                compression_type: self.compression_type,
+                compressor: Compressor::new(self.compressor.level()),
+                decompressor: Decompressor::new(),
+                encryptor: self.encryptor.clone(),
+                decryptor: self.decryptor.clone(),
+                tracker: self.tracker.clone(),
+                limits: self.limits,
+                read_buffer: buffer::clone_cell(&self.read_buffer),
+                write_buffer: Vec::new(),
             }),
-            _ => Err(ProtocolError::CloneError),
+            Err(e) => Err(ProtocolError::CloneError(e)),
         }
     }
 }
 
 impl<T: Read + Write> DataReader for MinecraftConnection<T> {
+    fn max_string_length(&self) -> usize {
+        self.limits.max_string_length
+    }
+
     fn read_bytes(&mut self, size: usize) -> Result<Vec<u8>, ProtocolError> {
-        let mut buf = vec![0; size];
-        match self.stream.read_exact(&mut buf) {
-            Ok(_) => Ok(buf),
-            Err(_) => Err(ProtocolError::ReadError),
-        }
+        let (stream, decryptor) = (&mut self.stream, &mut self.decryptor);
+        buffer::with_cell(&mut self.read_buffer, |i| i.read_bytes(stream, decryptor, size))
     }
 }
 
 impl<T: Read + Write> DataWriter for MinecraftConnection<T> {
+    /// Write bytes after queued packets and flush them, see [`flush`](MinecraftConnection::flush)
     fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), ProtocolError> {
-        match self.stream.write_all(bytes) {
-            Ok(_) => Ok(()),
-            Err(_) => Err(ProtocolError::WriteError),
-        }
+        self.write_buffer.extend_from_slice(bytes);
+        self.flush()
     }
 }
 
@@ -153,7 +402,14 @@ impl<T: Read + Write> MinecraftConnection<T> {
             is_alive: Arc::new(AtomicBool::new(true)),
             #[cfg(not(feature = "atomic_clone"))]
             is_alive: true,
-            compression_type: 1,
+            compressor: Compressor::new(1),
+            decompressor: Decompressor::new(),
+            encryptor: encryption::new_cell(),
+            decryptor: encryption::new_cell(),
+            tracker: state::new_cell(),
+            limits: Limits::default(),
+            read_buffer: buffer::new_cell(),
+            write_buffer: Vec::new(),
         }
     }
 
@@ -203,9 +459,9 @@ impl<T: Read + Write> MinecraftConnection<T> {
         {
             let threshold = self.compression.load(Ordering::Relaxed);
             if threshold == usize::MAX {
-                return None
+                None
             } else {
-                return Some(threshold)
+                Some(threshold)
             }
         }
         #[cfg(not(feature = "atomic_clone"))]
@@ -220,7 +476,17 @@ impl<T: Read + Write> MinecraftConnection<T> {
     /// 1 is fast compression
     /// 6 is normal compression
     pub fn set_compression_type(&mut self, compression_type: u32) {
-        self.compression_type = compression_type;
+        self.compressor.set_level(compression_type);
+    }
+
+    /// Set limits of received data
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    /// Get limits of received data
+    pub fn limits(&self) -> Limits {
+        self.limits
     }
 
     /// Get compression type
@@ -229,10 +495,76 @@ impl<T: Read + Write> MinecraftConnection<T> {
     /// 1 is fast compression
     /// 6 is normal compression
     pub fn compression_type(&self) -> u32 {
-        self.compression_type
+        self.compressor.level()
+    }
+
+    /// Enable AES-128-CFB8 encryption with shared secret
+    ///
+    /// Everything read and written after this call is encrypted,
+    /// `shared_secret` must be 16 bytes long
+    pub fn enable_encryption(&mut self, shared_secret: &[u8]) -> Result<(), ProtocolError> {
+        let (encryptor, decryptor) = encryption::new_cipher(shared_secret)?;
+        encryption::set_cell(&mut self.encryptor, encryptor);
+        encryption::set_cell(&mut self.decryptor, decryptor);
+
+        // bytes after packet that enabled encryption may be already read
+        let decryptor = &mut self.decryptor;
+        buffer::with_cell(&mut self.read_buffer, |i| {
+            encryption::decrypt_cell(decryptor, i.available_mut())
+        });
+        Ok(())
+    }
+
+    /// Is encryption enabled
+    pub fn is_encrypted(&self) -> bool {
+        encryption::is_cell_set(&self.encryptor)
+    }
+
+    /// Get protocol state
+    pub fn state(&self) -> ConnectionState {
+        state::read_cell(&self.tracker, |i| i.state)
+    }
+
+    /// Set protocol state, for connections that don't start from handshake
+    pub fn set_state(&mut self, state: ConnectionState) {
+        state::with_cell(&mut self.tracker, |i| i.state = state);
+    }
+
+    /// Get side of connection, `None` until handshake is read or written
+    pub fn side(&self) -> Option<Side> {
+        state::read_cell(&self.tracker, |i| i.side)
+    }
+
+    /// Set side of connection
+    pub fn set_side(&mut self, side: Side) {
+        state::with_cell(&mut self.tracker, |i| i.side = Some(side));
+    }
+
+    /// Get protocol version, `None` until handshake is read or written
+    pub fn protocol_version(&self) -> Option<i32> {
+        state::read_cell(&self.tracker, |i| i.protocol_version)
+    }
+
+    /// Set protocol version, for connections that don't start from handshake
+    pub fn set_protocol_version(&mut self, protocol_version: i32) {
+        state::with_cell(&mut self.tracker, |i| i.protocol_version = Some(protocol_version));
+    }
+
+    /// Get registry of packet ids
+    pub fn registry(&self) -> Option<Arc<ProtocolRegistry>> {
+        state::read_cell(&self.tracker, |i| i.registry.clone())
+    }
+
+    /// Set registry of packet ids, used for state transitions and
+    /// [`send_versioned`](Self::send_versioned) / [`recv_versioned`](Self::recv_versioned)
+    pub fn set_registry(&mut self, registry: Arc<ProtocolRegistry>) {
+        state::with_cell(&mut self.tracker, |i| i.registry = Some(registry));
     }
 
     /// Get mutable reference of stream
+    ///
+    /// Stream doesn't have bytes that connection has already read to buffer
+    /// and bytes of packets that are not flushed yet
     pub fn get_mut(&mut self) -> &mut T {
         &mut self.stream
     }
@@ -242,29 +574,84 @@ impl<T: Read + Write> MinecraftConnection<T> {
         &self.stream
     }
 
-    /// Read [`Packet`](Packet) from connection
+    /// Read [`Packet`](Packet) from connection, updates [`state`](Self::state)
     pub fn read_packet(&mut self) -> Result<Packet, ProtocolError> {
+        let packet = self.read_frame()?;
+        state::with_cell(&mut self.tracker, |i| i.update(false, &packet));
+        Ok(packet)
+    }
+
+    /// Read [`Packet`](Packet) which data shares memory with read buffer, updates
+    /// [`state`](Self::state)
+    ///
+    /// Data of uncompressed packets is not copied, so packet can be forwarded without copying
+    #[cfg(feature = "bytes")]
+    pub fn read_packet_bytes(&mut self) -> Result<Packet<bytes::BytesMut>, ProtocolError> {
+        let packet = self.read_frame()?;
+        state::with_cell(&mut self.tracker, |i| i.update(false, &packet));
+        Ok(packet)
+    }
+
+    /// Write [`Packet`](Packet) to connection and flush it, updates [`state`](Self::state)
+    pub fn write_packet<B: PacketData>(&mut self, packet: &Packet<B>) -> Result<(), ProtocolError> {
+        self.queue_packet(packet)?;
+        self.flush()
+    }
+
+    /// Add [`Packet`](Packet) to write buffer, updates [`state`](Self::state)
+    ///
+    /// Packet is sent on next [`flush`](Self::flush), so many packets can be sent at once
+    pub fn queue_packet<B: PacketData>(&mut self, packet: &Packet<B>) -> Result<(), ProtocolError> {
         if !self.is_alive() {
             return Err(ProtocolError::ConnectionClosedError);
         }
 
-        #[cfg(feature = "atomic_clone")]
+        let length = self.write_buffer.len();
+        let compression = self.compression();
+        if let Err(e) =
+            encode_packet_with(&mut self.compressor, &mut self.write_buffer, compression, packet)
         {
-            return match read_packet_atomic(
-                            &mut self.stream,
-                            self.compression.clone(),
-                            Ordering::Relaxed,
-                        ) {
-                Err(ProtocolError::ConnectionClosedError) => {
-                    self.set_alive(false);
-                    Err(ProtocolError::ConnectionClosedError)
-                },
-                i => i
-            };
+            self.write_buffer.truncate(length);
+            return Err(e);
         }
 
-        #[cfg(not(feature = "atomic_clone"))]
-        match read_packet(&mut self.stream, self.compression) {
+        state::with_cell(&mut self.tracker, |i| i.update(true, packet));
+        Ok(())
+    }
+
+    /// Write queued packets to stream, see [`queue_packet`](Self::queue_packet)
+    pub fn flush(&mut self) -> Result<(), ProtocolError> {
+        if self.write_buffer.is_empty() {
+            return Ok(());
+        }
+        if !self.is_alive() {
+            return Err(ProtocolError::ConnectionClosedError);
+        }
+
+        let (stream, encryptor) = (&mut self.stream, &mut self.encryptor);
+        let result = encryption::write_encrypted(stream, encryptor, &mut self.write_buffer);
+        self.write_buffer.clear();
+        result
+    }
+
+    fn read_frame<B>(&mut self) -> Result<Packet<B>, ProtocolError>
+    where
+        B: PacketData + From<PacketBuffer>,
+    {
+        if !self.is_alive() {
+            return Err(ProtocolError::ConnectionClosedError);
+        }
+
+        let compression = self.compression();
+        let limits = self.limits;
+        let (stream, decryptor) = (&mut self.stream, &mut self.decryptor);
+        let decompressor = &mut self.decompressor;
+
+        match buffer::with_cell(&mut self.read_buffer, |i| {
+            i.read_frame(stream, decryptor, limits.max_frame_length, |frame| {
+                decode_packet_with(decompressor, frame, compression, &limits)
+            })
+        }) {
             Err(ProtocolError::ConnectionClosedError) => {
                 self.set_alive(false);
                 Err(ProtocolError::ConnectionClosedError)
@@ -272,56 +659,75 @@ impl<T: Read + Write> MinecraftConnection<T> {
             i => i
         }
     }
+}
 
-    /// Write [`Packet`](Packet) to connection
-    pub fn write_packet(&mut self, packet: &Packet) -> Result<(), ProtocolError> {
-        if !self.is_alive() {
-            return Err(ProtocolError::ConnectionClosedError);
-        }
-        
-        #[cfg(feature = "atomic_clone")]
-        {
-            return write_packet_atomic(
-                &mut self.stream,
-                self.compression.clone(),
-                Ordering::Relaxed,
-                self.compression_type,
-                packet,
-            )
-        }
+impl<T: Read + Write> MinecraftConnection<T> {
+    /// Write typed packet to connection
+    pub fn send<P: McPacket>(&mut self, packet: &P) -> Result<(), ProtocolError> {
+        self.write_packet(&packet.to_packet()?)
+    }
 
-        #[cfg(not(feature = "atomic_clone"))]
-        {
-            write_packet(&mut self.stream, self.compression, self.compression_type, packet)
-        }
+    /// Add typed packet to write buffer, see [`queue_packet`](Self::queue_packet)
+    pub fn queue<P: McPacket>(&mut self, packet: &P) -> Result<(), ProtocolError> {
+        self.queue_packet(&packet.to_packet()?)
+    }
+
+    /// Read typed packet from connection, fails if packet has other id
+    pub fn recv<P: McPacket>(&mut self) -> Result<P, ProtocolError> {
+        P::from_packet(self.read_packet()?)
+    }
+
+    /// Read packet and decode it by current state and side
+    ///
+    /// Side is server if it is not known yet, because only client sends handshake.
+    /// Ids are taken from registry if connection has one
+    pub fn recv_any(&mut self) -> Result<AnyPacket, ProtocolError> {
+        let tracker = state::read_cell(&self.tracker, |i| i.clone());
+        let packet = self.read_packet()?;
+        let direction = state::read_cell(&self.tracker, |i| i.inbound());
+        tracker.decode_any(direction, packet)
+    }
+
+    /// Write typed packet with id of connection protocol version, see [`set_registry`](Self::set_registry)
+    pub fn send_versioned<P: RegistryPacket>(&mut self, packet: &P) -> Result<(), ProtocolError> {
+        let packet = state::read_cell(&self.tracker, |i| i.encode(packet))?;
+        self.write_packet(&packet)
+    }
+
+    /// Read typed packet with id of connection protocol version, see [`set_registry`](Self::set_registry)
+    pub fn recv_versioned<P: RegistryPacket>(&mut self) -> Result<P, ProtocolError> {
+        let packet = self.read_packet()?;
+        state::read_cell(&self.tracker, |i| i.decode(packet))
     }
 }
 
 impl<T: Read + Write + Clone> MinecraftConnection<T> {
-    /// Clone MinecraftConnection with compression and stream
+    /// Clone MinecraftConnection with compression, encryption and stream
+    ///
+    /// Without `atomic_clone` feature clone starts with empty read buffer,
+    /// bytes that are already read by this connection are not seen by clone
     pub fn clone(&mut self) -> MinecraftConnection<T> {
         MinecraftConnection {
             stream: self.stream.clone(),
             compression: self.compression.clone(),
             is_alive: self.is_alive.clone(),
-            compression_type: self.compression_type,
+            compressor: Compressor::new(self.compressor.level()),
+            decompressor: Decompressor::new(),
+            encryptor: self.encryptor.clone(),
+            decryptor: self.decryptor.clone(),
+            tracker: self.tracker.clone(),
+            limits: self.limits,
+            read_buffer: buffer::clone_cell(&self.read_buffer),
+            write_buffer: Vec::new(),
         }
     }
 }
 
-fn compress_zlib(bytes: &[u8], compression: u32) -> Result<Vec<u8>, ProtocolError> {
-    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::new(compression));
-    encoder.write_all(bytes).or(Err(ProtocolError::ZlibError))?;
-    encoder.finish().or(Err(ProtocolError::ZlibError))
-}
-
-fn decompress_zlib(bytes: &[u8]) -> Result<Vec<u8>, ProtocolError> {
-    let mut decoder = ZlibDecoder::new(bytes);
-    let mut output = Vec::new();
-    decoder
-        .read_to_end(&mut output)
-        .or(Err(ProtocolError::ZlibError))?;
-    Ok(output)
+pub(crate) fn resolve_addr(addr: &str) -> Result<SocketAddr, ProtocolError> {
+    match addr.to_socket_addrs() {
+        Ok(mut i) => i.next().ok_or(ProtocolError::AddressParseError(None)),
+        Err(e) => Err(ProtocolError::AddressParseError(Some(e))),
+    }
 }
 
 /// MinecraftConnection shorter alias
@@ -341,23 +747,111 @@ pub fn read_packet<T: Read>(
     stream: &mut T,
     compression: Option<usize>
 ) -> Result<Packet, ProtocolError> {
-    let mut data: Vec<u8>;
+    read_packet_with_limits(stream, compression, &Limits::default())
+}
 
-    let packet_length = stream.read_usize_varint_size()?;
+/// Read [`Packet`](Packet) from stream, fails if frame is longer than `limits` allow
+///
+/// `compression` is compression threshold, None means that compression is disabled
+pub fn read_packet_with_limits<T: Read>(
+    stream: &mut T,
+    compression: Option<usize>,
+    limits: &Limits,
+) -> Result<Packet, ProtocolError> {
+    let packet_length = stream.read_usize_varint()?;
+    check_length("Frame", packet_length, limits.max_frame_length)?;
+    let frame = stream.read_bytes(packet_length)?;
+
+    decode_packet_buffer(frame, compression, limits)
+}
+
+/// Decode [`Packet`](Packet) from frame without length prefix
+///
+/// `compression` is compression threshold, None means that compression is disabled
+pub fn decode_packet(frame: &[u8], compression: Option<usize>) -> Result<Packet, ProtocolError> {
+    decode_packet_with_limits(frame, compression, &Limits::default())
+}
+
+/// Decode [`Packet`](Packet) from frame without length prefix,
+/// fails if decompressed packet is longer than `limits` allow or if frame breaks
+/// [`strict`](Limits::strict) rules
+///
+/// `compression` is compression threshold, None means that compression is disabled
+pub fn decode_packet_with_limits(
+    frame: &[u8],
+    compression: Option<usize>,
+    limits: &Limits,
+) -> Result<Packet, ProtocolError> {
+    decode_packet_buffer(frame.to_vec(), compression, limits)
+}
 
-    if compression.is_some() {
-        let data_length = stream.read_usize_varint_size()?;
+/// Decode [`Packet`](Packet) from frame without length prefix,
+/// same as [`decode_packet_with_limits`](decode_packet_with_limits) but uncompressed
+/// packet data is not copied
+pub fn decode_packet_buffer<B: PacketData>(
+    frame: B,
+    compression: Option<usize>,
+    limits: &Limits,
+) -> Result<Packet<B>, ProtocolError> {
+    decode_packet_with(&mut Decompressor::new(), frame, compression, limits)
+}
 
-        data = stream.read_bytes(packet_length.0 - data_length.1)?;
+/// Decode [`Packet`](Packet) from frame without length prefix with `decompressor`,
+/// so its state is reused between packets
+pub fn decode_packet_with<B: PacketData>(
+    decompressor: &mut Decompressor,
+    mut frame: B,
+    compression: Option<usize>,
+    limits: &Limits,
+) -> Result<Packet<B>, ProtocolError> {
+    let mut packet = if let Some(threshold) = compression {
+        let (data_length, data_length_size) = Cursor::new(&frame[..])
+            .read_usize_varint_size()
+            .map_err(|e| match e {
+                ProtocolError::ConnectionClosedError => ProtocolError::DataRanOutError {
+                    field: Some("Data Length"),
+                    packet_id: None,
+                    expected: frame.len() + 1,
+                    actual: frame.len(),
+                },
+                e => e,
+            })?;
+        frame.advance(data_length_size);
+
+        if data_length != 0 {
+            if limits.strict && data_length < threshold {
+                return Err(ProtocolError::CompressionThresholdError {
+                    length: data_length,
+                    threshold,
+                    compressed: true,
+                });
+            }
 
-        if data_length.0 != 0 {
-            data = decompress_zlib(&data)?;
+            let data =
+                decompressor.decompress(&frame, data_length, limits.max_decompressed_length)?;
+            if limits.strict && data.len() != data_length {
+                return Err(ProtocolError::DataLengthError {
+                    expected: data_length,
+                    actual: data.len(),
+                });
+            }
+            Packet::from_buffer(B::from_vec(data))?
+        } else {
+            if limits.strict && frame.len() >= threshold {
+                return Err(ProtocolError::CompressionThresholdError {
+                    length: frame.len(),
+                    threshold,
+                    compressed: false,
+                });
+            }
+            Packet::from_buffer(frame)?
         }
     } else {
-        data = stream.read_bytes(packet_length.0)?;
-    }
-
-    Ok(Packet::from_data(&data)?)
+        Packet::from_buffer(frame)?
+    };
+    packet.set_max_string_length(limits.max_string_length);
+    packet.set_strict(limits.strict);
+    Ok(packet)
 }
 
 /// Write [`Packet`](Packet) to stream
@@ -376,38 +870,85 @@ pub fn write_packet<T: Write>(
     compression_type: u32,
     packet: &Packet,
 ) -> Result<(), ProtocolError> {
-    let mut buf = Vec::new();
+    stream.write_bytes(&encode_packet(compression, compression_type, packet)?)
+}
 
-    let mut data_buf = Vec::new();
-    data_buf.write_u8_varint(packet.id())?;
-    data_buf.write_bytes(packet.get_bytes())?;
+/// Encode [`Packet`](Packet) to frame with length prefix
+///
+/// `compression` is compression threshold, None means that compression is disabled
+///
+/// `compression_type` is integer from 0 (none) to 9 (longest)
+/// 1 is fast compression
+/// 6 is normal compression
+pub fn encode_packet(
+    compression: Option<usize>,
+    compression_type: u32,
+    packet: &Packet,
+) -> Result<Vec<u8>, ProtocolError> {
+    let mut buf = Vec::new();
+    encode_packet_into(&mut buf, compression, compression_type, packet)?;
+    Ok(buf)
+}
 
-    if let Some(compression) = compression {
-        let mut packet_buf = Vec::new();
+/// Encode [`Packet`](Packet) to frame with length prefix and append it to `buf`
+///
+/// `compression` is compression threshold, None means that compression is disabled
+///
+/// `compression_type` is integer from 0 (none) to 9 (longest)
+/// 1 is fast compression
+/// 6 is normal compression
+pub fn encode_packet_into(
+    buf: &mut Vec<u8>,
+    compression: Option<usize>,
+    compression_type: u32,
+    packet: &Packet,
+) -> Result<(), ProtocolError> {
+    encode_packet_with(&mut Compressor::new(compression_type), buf, compression, packet)
+}
 
-        if data_buf.len() >= compression {
-            let compressed_data = compress_zlib(&data_buf, compression_type)?;
-            packet_buf.write_usize_varint(data_buf.len())?;
-            packet_buf
-                .write_all(&compressed_data)
-                .or(Err(ProtocolError::WriteError))?;
-        } else {
-            packet_buf.write_usize_varint(0)?;
-            packet_buf.write_bytes(&data_buf)?;
+/// Encode [`Packet`](Packet) to frame with length prefix and append it to `buf`,
+/// `compressor` state is reused between packets
+///
+/// `compression` is compression threshold, None means that compression is disabled
+pub fn encode_packet_with<B: PacketData>(
+    compressor: &mut Compressor,
+    buf: &mut Vec<u8>,
+    compression: Option<usize>,
+    packet: &Packet<B>,
+) -> Result<(), ProtocolError> {
+    let mut id = Vec::with_capacity(5);
+    id.write_varint(packet.id())?;
+    let body = packet.get_bytes();
+    let data_length = id.len() + body.len();
+
+    match compression {
+        Some(threshold) if data_length >= threshold => {
+            let compressed = compressor.compress(&[&id, body])?;
+            buf.write_usize_varint(varint_length(data_length) + compressed.len())?;
+            buf.write_usize_varint(data_length)?;
+            buf.extend_from_slice(compressed);
+        }
+        Some(_) => {
+            buf.write_usize_varint(data_length + 1)?;
+            buf.push(0);
+            buf.extend_from_slice(&id);
+            buf.extend_from_slice(body);
+        }
+        None => {
+            buf.write_usize_varint(data_length)?;
+            buf.extend_from_slice(&id);
+            buf.extend_from_slice(body);
         }
-
-        buf.write_usize_varint(packet_buf.len())?;
-        buf.write_bytes(&packet_buf)?;
-    } else {
-        buf.write_usize_varint(data_buf.len())?;
-        buf.write_bytes(&data_buf)?;
     }
 
-    stream.write_bytes(&buf)?;
-
     Ok(())
 }
 
+/// Size of VarInt in bytes
+fn varint_length(value: usize) -> usize {
+    ((usize::BITS - value.leading_zeros()).max(1) as usize).div_ceil(7)
+}
+
 /// Read [`Packet`](Packet) from stream
 ///
 /// `compression` here is atomic usize
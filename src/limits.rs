@@ -0,0 +1,69 @@
+//! Limits of received data, checked before memory for data is allocated
+
+use crate::ProtocolError;
+
+/// Max frame length, same as vanilla (frame length is VarInt of 3 bytes)
+pub const DEFAULT_MAX_FRAME_LENGTH: usize = (1 << 21) - 1;
+
+/// Max length of decompressed packet, same as vanilla
+pub const DEFAULT_MAX_DECOMPRESSED_LENGTH: usize = 8 * 1024 * 1024;
+
+/// Max length of String in bytes, 32767 characters of 3 bytes
+pub const DEFAULT_MAX_STRING_LENGTH: usize = 32767 * 3;
+
+/// Limits of received data, exceeding them fails with
+/// [`LengthLimitError`](ProtocolError::LengthLimitError)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Limits {
+    /// Max length of frame without length prefix
+    pub max_frame_length: usize,
+    /// Max length of packet id and data after decompression
+    pub max_decompressed_length: usize,
+    /// Max length of String in bytes
+    pub max_string_length: usize,
+    /// Validate packets like vanilla: declared Data Length must match decompressed length,
+    /// packets must be compressed only if they are not shorter than compression threshold
+    /// and typed packets must have no bytes after body
+    pub strict: bool,
+}
+
+impl Limits {
+    /// Create default limits
+    pub fn new() -> Limits {
+        Limits::default()
+    }
+
+    /// Create limits that don't limit anything
+    pub fn unlimited() -> Limits {
+        Limits {
+            max_frame_length: usize::MAX,
+            max_decompressed_length: usize::MAX,
+            max_string_length: usize::MAX,
+            strict: false,
+        }
+    }
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_frame_length: DEFAULT_MAX_FRAME_LENGTH,
+            max_decompressed_length: DEFAULT_MAX_DECOMPRESSED_LENGTH,
+            max_string_length: DEFAULT_MAX_STRING_LENGTH,
+            strict: false,
+        }
+    }
+}
+
+/// Fail if `length` of `field` is more than `max`
+pub(crate) fn check_length(
+    field: &'static str,
+    length: usize,
+    max: usize,
+) -> Result<(), ProtocolError> {
+    if length > max {
+        Err(ProtocolError::LengthLimitError { field, length, max })
+    } else {
+        Ok(())
+    }
+}
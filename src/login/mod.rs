@@ -0,0 +1,149 @@
+//! Login helpers, offline mode, see [`online`](online) for online mode
+//!
+//! Both sides of login start after handshake with next state Login and end in Configuration state:
+//! - client sends Login Start
+//! - server may send Set Compression, compression is enabled right after it
+//! - server sends Login Success, client answers with Login Acknowledged
+
+pub mod online;
+
+use crate::{
+    packets::login::{
+        LoginAcknowledged, LoginDisconnect, LoginPluginResponse, LoginStart, LoginSuccess,
+        Property, SetCompression,
+    },
+    packets::AnyPacket,
+    text::TextComponent,
+    MinecraftConnection, ProtocolError,
+};
+use md5::{Digest, Md5};
+use std::io::{Read, Write};
+use uuid::{Builder, Uuid};
+
+/// Profile of player that logged in
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameProfile {
+    pub id: Uuid,
+    pub name: String,
+    /// Properties like skin textures, empty in offline mode
+    pub properties: Vec<Property>,
+}
+
+impl GameProfile {
+    /// Profile of offline player
+    pub fn offline(name: &str) -> GameProfile {
+        GameProfile {
+            id: offline_uuid(name),
+            name: name.to_string(),
+            properties: Vec::new(),
+        }
+    }
+}
+
+/// Player name is valid: 1 to 16 characters of `A-Z`, `a-z`, `0-9` and `_`
+pub fn is_valid_name(name: &str) -> bool {
+    (1..=16).contains(&name.len())
+        && name.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_')
+}
+
+/// UUID of offline player, version 3 UUID of `OfflinePlayer:<name>`
+pub fn offline_uuid(name: &str) -> Uuid {
+    let hash = Md5::digest(format!("OfflinePlayer:{name}"));
+    Builder::from_md5_bytes(hash.into()).into_uuid()
+}
+
+impl<T: Read + Write> MinecraftConnection<T> {
+    /// Login client in offline mode, server side
+    ///
+    /// Must be called after handshake is read. Compression is enabled with `threshold` if it is set
+    pub fn accept_offline_login(
+        &mut self,
+        threshold: Option<usize>,
+    ) -> Result<GameProfile, ProtocolError> {
+        let start = self.recv_login_start()?;
+        self.finish_login(GameProfile::offline(&start.name), threshold)
+    }
+
+    /// Receive Login Start, client is disconnected if player name is invalid
+    pub(crate) fn recv_login_start(&mut self) -> Result<LoginStart, ProtocolError> {
+        let start = self.recv_versioned::<LoginStart>()?;
+        if !is_valid_name(&start.name) {
+            self.send_versioned(&LoginDisconnect {
+                reason: TextComponent::text("Invalid player name").to_json(),
+            })?;
+            return Err(ProtocolError::LoginError("invalid player name"));
+        }
+        Ok(start)
+    }
+
+    /// Enable compression, send Login Success and wait for Login Acknowledged
+    fn finish_login(
+        &mut self,
+        profile: GameProfile,
+        threshold: Option<usize>,
+    ) -> Result<GameProfile, ProtocolError> {
+        if let Some(threshold) = threshold {
+            let packet_threshold = i32::try_from(threshold)
+                .map_err(|_| ProtocolError::LoginError("compression threshold is too big"))?;
+            self.send_versioned(&SetCompression {
+                threshold: packet_threshold,
+            })?;
+            self.set_compression(Some(threshold));
+        }
+
+        self.send_versioned(&LoginSuccess {
+            uuid: profile.id,
+            username: profile.name.clone(),
+            properties: profile.properties.clone(),
+        })?;
+        self.recv_versioned::<LoginAcknowledged>()?;
+
+        Ok(profile)
+    }
+
+    /// Login to server in offline mode, client side
+    ///
+    /// Must be called after handshake with next state Login is sent.
+    /// Compression is enabled when server sends Set Compression, plugin requests are declined
+    pub fn login_offline(&mut self, name: &str) -> Result<GameProfile, ProtocolError> {
+        if !is_valid_name(name) {
+            return Err(ProtocolError::LoginError("invalid player name"));
+        }
+
+        self.send_versioned(&LoginStart {
+            name: name.to_string(),
+            uuid: offline_uuid(name),
+        })?;
+
+        loop {
+            match self.recv_any()? {
+                AnyPacket::SetCompression(packet) => {
+                    self.set_compression(usize::try_from(packet.threshold).ok());
+                }
+                AnyPacket::LoginPluginRequest(packet) => {
+                    self.send_versioned(&LoginPluginResponse {
+                        message_id: packet.message_id,
+                        data: None,
+                    })?;
+                }
+                AnyPacket::LoginSuccess(packet) => {
+                    self.send_versioned(&LoginAcknowledged)?;
+                    return Ok(GameProfile {
+                        id: packet.uuid,
+                        name: packet.username,
+                        properties: packet.properties,
+                    });
+                }
+                AnyPacket::LoginDisconnect(LoginDisconnect { reason }) => {
+                    return Err(ProtocolError::DisconnectError(Box::new(
+                        TextComponent::from_json(&reason).unwrap_or_else(|_| reason.into()),
+                    )));
+                }
+                AnyPacket::EncryptionRequest(_) => {
+                    return Err(ProtocolError::LoginError("server is in online mode"));
+                }
+                _ => return Err(ProtocolError::LoginError("unexpected packet")),
+            }
+        }
+    }
+}
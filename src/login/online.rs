@@ -0,0 +1,242 @@
+//! Online mode login, server side
+//!
+//! Server sends its RSA public key in Encryption Request, client answers with shared secret
+//! encrypted by this key and server enables encryption with it. Then server asks session server
+//! whether player has joined with [`server_hash`](server_hash), session server is called through
+//! [`SessionServer`](SessionServer) trait so any HTTP client can be used
+//!
+//! `ServerKey` and `MinecraftConnection::accept_online_login` need `rsa` feature.
+//! PKCS#1 v1.5 decryption of `rsa` crate is not constant time
+//! ([RUSTSEC-2023-0071](https://rustsec.org/advisories/RUSTSEC-2023-0071)), timing of login
+//! failures may leak key to attacker that can measure it over network
+
+use super::GameProfile;
+use crate::{packets::login::Property, ProtocolError};
+use serde_json::Value;
+use sha1::{Digest, Sha1};
+use std::{fmt::Write as _, net::IpAddr};
+use uuid::Uuid;
+
+#[cfg(feature = "rsa")]
+use crate::{
+    packets::login::{EncryptionRequest, EncryptionResponse},
+    MinecraftConnection,
+};
+#[cfg(feature = "rsa")]
+use rsa::{
+    pkcs8::EncodePublicKey,
+    rand_core::{OsRng, RngCore},
+    Pkcs1v15Encrypt, RsaPrivateKey,
+};
+#[cfg(feature = "rsa")]
+use std::io::{Read, Write};
+
+/// Address of Mojang session server
+pub const MOJANG_SESSION_SERVER: &str = "https://sessionserver.mojang.com";
+
+/// Size of RSA key, same as vanilla server
+#[cfg(feature = "rsa")]
+pub const KEY_BITS: usize = 1024;
+
+/// RSA keypair of server
+#[cfg(feature = "rsa")]
+#[derive(Debug, Clone)]
+pub struct ServerKey {
+    private_key: RsaPrivateKey,
+    public_key: Vec<u8>,
+}
+
+#[cfg(feature = "rsa")]
+impl ServerKey {
+    /// Generate new keypair of [`KEY_BITS`](KEY_BITS) bits
+    pub fn generate() -> Result<ServerKey, ProtocolError> {
+        let private_key = RsaPrivateKey::new(&mut OsRng, KEY_BITS)
+            .map_err(|_| ProtocolError::LoginError("can't generate server key"))?;
+        ServerKey::from_private_key(private_key)
+    }
+
+    /// Create keypair from private key
+    pub fn from_private_key(private_key: RsaPrivateKey) -> Result<ServerKey, ProtocolError> {
+        let public_key = private_key
+            .to_public_key()
+            .to_public_key_der()
+            .map_err(|_| ProtocolError::LoginError("can't encode server key"))?
+            .into_vec();
+        Ok(ServerKey { private_key, public_key })
+    }
+
+    /// Public key encoded as DER, sent in Encryption Request
+    pub fn public_key(&self) -> &[u8] {
+        &self.public_key
+    }
+
+    /// Decrypt data that client encrypted with public key
+    ///
+    /// Not constant time, see [RUSTSEC-2023-0071](https://rustsec.org/advisories/RUSTSEC-2023-0071)
+    pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>, ProtocolError> {
+        self.private_key
+            .decrypt(Pkcs1v15Encrypt, data)
+            .map_err(|_| ProtocolError::LoginError("can't decrypt encryption response"))
+    }
+}
+
+/// Session server that checks that player has joined server
+pub trait SessionServer {
+    /// Get profile of player if player has joined server with `server_hash`, `None` if not
+    ///
+    /// See [`has_joined_url`](has_joined_url) and
+    /// [`GameProfile::from_json`](GameProfile::from_json)
+    fn has_joined(
+        &self,
+        username: &str,
+        server_hash: &str,
+        ip: Option<IpAddr>,
+    ) -> Result<Option<GameProfile>, ProtocolError>;
+}
+
+/// URL of hasJoined request to session server,
+/// like [`MOJANG_SESSION_SERVER`](MOJANG_SESSION_SERVER)
+pub fn has_joined_url(
+    session_server: &str,
+    username: &str,
+    server_hash: &str,
+    ip: Option<IpAddr>,
+) -> String {
+    let mut url = format!(
+        "{}/session/minecraft/hasJoined?username={}&serverId={}",
+        session_server.trim_end_matches('/'),
+        encode_query(username),
+        encode_query(server_hash)
+    );
+    if let Some(ip) = ip {
+        let _ = write!(url, "&ip={}", encode_query(&ip.to_string()));
+    }
+    url
+}
+
+/// Percent-encode query value
+fn encode_query(value: &str) -> String {
+    let mut result = String::new();
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-_.~".contains(&byte) {
+            result.push(byte as char);
+        } else {
+            let _ = write!(result, "%{byte:02X}");
+        }
+    }
+    result
+}
+
+/// Server hash sent to session server, SHA-1 as signed hexadecimal number like Java `BigInteger`
+pub fn server_hash(server_id: &str, shared_secret: &[u8], public_key: &[u8]) -> String {
+    let mut hash: [u8; 20] = Sha1::new()
+        .chain_update(server_id.as_bytes())
+        .chain_update(shared_secret)
+        .chain_update(public_key)
+        .finalize()
+        .into();
+
+    let negative = hash[0] & 0x80 != 0;
+    if negative {
+        // two's complement
+        let mut carry = true;
+        for byte in hash.iter_mut().rev() {
+            *byte = !*byte;
+            if carry {
+                (*byte, carry) = byte.overflowing_add(1);
+            }
+        }
+    }
+
+    let mut digits = String::new();
+    for byte in hash {
+        let _ = write!(digits, "{byte:02x}");
+    }
+    let digits = digits.trim_start_matches('0');
+
+    match (negative, digits.is_empty()) {
+        (_, true) => "0".to_string(),
+        (true, false) => format!("-{digits}"),
+        (false, false) => digits.to_string(),
+    }
+}
+
+impl GameProfile {
+    /// Parse profile from JSON of session server
+    pub fn from_json(json: &str) -> Result<GameProfile, ProtocolError> {
+        let value: Value = serde_json::from_str(json).map_err(ProtocolError::JsonError)?;
+        GameProfile::from_value(&value)
+    }
+
+    /// Read profile from JSON value of session server
+    pub fn from_value(value: &Value) -> Result<GameProfile, ProtocolError> {
+        let error = || ProtocolError::LoginError("invalid game profile");
+        let get_str =
+            |value: &Value, field| value.get(field).and_then(Value::as_str).map(str::to_string);
+
+        let id = get_str(value, "id")
+            .and_then(|i| Uuid::parse_str(&i).ok())
+            .ok_or_else(error)?;
+        let name = get_str(value, "name").ok_or_else(error)?;
+
+        let mut properties = Vec::new();
+        if let Some(list) = value.get("properties").and_then(Value::as_array) {
+            for property in list {
+                properties.push(Property {
+                    name: get_str(property, "name").ok_or_else(error)?,
+                    value: get_str(property, "value").ok_or_else(error)?,
+                    signature: get_str(property, "signature"),
+                });
+            }
+        }
+
+        Ok(GameProfile { id, name, properties })
+    }
+}
+
+/// Compare bytes in time that doesn't depend on their contents
+#[cfg(feature = "rsa")]
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+#[cfg(feature = "rsa")]
+impl<T: Read + Write> MinecraftConnection<T> {
+    /// Login client in online mode, server side
+    ///
+    /// Must be called after handshake is read. `ip` of client is sent to session server
+    /// if it is set, compression is enabled with `threshold` if it is set
+    pub fn accept_online_login(
+        &mut self,
+        key: &ServerKey,
+        session_server: &impl SessionServer,
+        ip: Option<IpAddr>,
+        threshold: Option<usize>,
+    ) -> Result<GameProfile, ProtocolError> {
+        let start = self.recv_login_start()?;
+
+        let mut verify_token = vec![0; 4];
+        OsRng.fill_bytes(&mut verify_token);
+
+        self.send_versioned(&EncryptionRequest {
+            server_id: String::new(),
+            public_key: key.public_key().to_vec(),
+            verify_token: verify_token.clone(),
+            should_authenticate: true,
+        })?;
+
+        let response = self.recv_versioned::<EncryptionResponse>()?;
+        if !constant_time_eq(&key.decrypt(&response.verify_token)?, &verify_token) {
+            return Err(ProtocolError::LoginError("verify token doesn't match"));
+        }
+        let shared_secret = key.decrypt(&response.shared_secret)?;
+        self.enable_encryption(&shared_secret)?;
+
+        let hash = server_hash("", &shared_secret, key.public_key());
+        let profile = session_server
+            .has_joined(&start.name, &hash, ip)?
+            .ok_or(ProtocolError::LoginError("player is not authenticated"))?;
+
+        self.finish_login(profile, threshold)
+    }
+}
@@ -1,54 +1,111 @@
 //! Minecraft packet struct
 
 use crate::data::{DataReader, DataWriter};
+use crate::limits::DEFAULT_MAX_STRING_LENGTH;
 use crate::ProtocolError;
-use std::io::Cursor;
+use std::{
+    fmt::Debug,
+    io::Cursor,
+    ops::{Deref, DerefMut},
+};
+
+/// Buffer that connection reads frames to
+#[cfg(not(feature = "bytes"))]
+pub(crate) type PacketBuffer = Vec<u8>;
+
+/// Buffer that connection reads frames to, frames are split from it without copying
+#[cfg(feature = "bytes")]
+pub(crate) type PacketBuffer = bytes::BytesMut;
+
+/// Buffer of packet data, `Vec<u8>` or `BytesMut` with `bytes` feature
+pub trait PacketData:
+    Deref<Target = [u8]>
+    + DerefMut
+    + for<'a> From<&'a [u8]>
+    + Default
+    + Clone
+    + Debug
+{
+    /// Create buffer from `Vec<u8>` without copying
+    fn from_vec(data: Vec<u8>) -> Self;
+
+    /// Remove first `size` bytes, without copying for `BytesMut`
+    fn advance(&mut self, size: usize);
+
+    /// Resize buffer, new bytes are zero
+    fn resize_zeroed(&mut self, len: usize);
+
+    /// Append bytes to end of buffer
+    fn append_slice(&mut self, bytes: &[u8]);
+}
+
+impl PacketData for Vec<u8> {
+    fn from_vec(data: Vec<u8>) -> Self {
+        data
+    }
+
+    fn advance(&mut self, size: usize) {
+        self.drain(..size);
+    }
+
+    fn resize_zeroed(&mut self, len: usize) {
+        self.resize(len, 0);
+    }
+
+    fn append_slice(&mut self, bytes: &[u8]) {
+        self.extend_from_slice(bytes);
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl PacketData for bytes::BytesMut {
+    fn from_vec(data: Vec<u8>) -> Self {
+        bytes::Bytes::from(data).into()
+    }
+
+    fn advance(&mut self, size: usize) {
+        bytes::Buf::advance(self, size);
+    }
+
+    fn resize_zeroed(&mut self, len: usize) {
+        self.resize(len, 0);
+    }
+
+    fn append_slice(&mut self, bytes: &[u8]) {
+        self.extend_from_slice(bytes);
+    }
+}
 
 /// Minecraft packet
+///
+/// Data is `Vec<u8>` by default, with `bytes` feature it can be `BytesMut`
+/// that is read by `MinecraftConnection::read_packet_bytes`
 #[derive(Debug, Clone)]
-pub struct Packet {
-    id: u8,
-    cursor: Cursor<Vec<u8>>,
+pub struct Packet<B: PacketData = Vec<u8>> {
+    id: i32,
+    cursor: Cursor<B>,
+    max_string_length: usize,
+    strict: bool,
 }
 
 impl Packet {
-    /// Create new packet from id and buffer
-    pub fn new(id: u8, cursor: Cursor<Vec<u8>>) -> Packet {
-        Packet { id, cursor }
-    }
-
     /// Create new packet from raw packet (id + data)
     pub fn from_data(data: &[u8]) -> Result<Packet, ProtocolError> {
-        let mut cursor = Cursor::new(data);
-
-        let (packet_id, packet_id_size) = cursor.read_u8_varint_size()?;
-        let packet_data =
-            DataReader::read_bytes(&mut cursor, data.len() - packet_id_size as usize)?;
-
-        Ok(Packet {
-            id: packet_id,
-            cursor: Cursor::new(packet_data),
-        })
+        Packet::from_buffer(data.to_vec())
     }
 
     /// Create new packet from id and bytes in buffer
-    pub fn from_bytes(id: u8, data: &[u8]) -> Packet {
-        Packet {
-            id,
-            cursor: Cursor::new(data.to_vec()),
-        }
+    pub fn from_bytes(id: i32, data: &[u8]) -> Packet {
+        Packet::new(id, Cursor::new(data.to_vec()))
     }
 
     /// Create new packet with id and empty buffer
-    pub fn empty(id: u8) -> Packet {
-        Packet {
-            id,
-            cursor: Cursor::new(Vec::new()),
-        }
+    pub fn empty(id: i32) -> Packet {
+        Packet::new(id, Cursor::new(Vec::new()))
     }
 
     /// Build packet with lambda
-    pub fn build<F>(id: u8, builder: F) -> Result<Packet, ProtocolError>
+    pub fn build<F>(id: i32, builder: F) -> Result<Packet, ProtocolError>
     where
         F: FnOnce(&mut Packet) -> Result<(), ProtocolError>,
     {
@@ -56,22 +113,77 @@ impl Packet {
         builder(&mut packet)?;
         Ok(packet)
     }
+}
+
+impl<B: PacketData> Packet<B> {
+    /// Create new packet from id and buffer
+    pub fn new(id: i32, cursor: Cursor<B>) -> Packet<B> {
+        Packet {
+            id,
+            cursor,
+            max_string_length: DEFAULT_MAX_STRING_LENGTH,
+            strict: false,
+        }
+    }
+
+    /// Create new packet from raw packet (id + data) without copying data
+    pub fn from_buffer(mut data: B) -> Result<Packet<B>, ProtocolError> {
+        let (packet_id, packet_id_size) =
+            Cursor::new(&data[..]).read_varint_size().map_err(|e| match e {
+                ProtocolError::ConnectionClosedError => ProtocolError::DataRanOutError {
+                    field: Some("Packet ID"),
+                    packet_id: None,
+                    expected: data.len() + 1,
+                    actual: data.len(),
+                },
+                e => e,
+            })?;
+        data.advance(packet_id_size);
+
+        Ok(Packet::new(packet_id, Cursor::new(data)))
+    }
 
     /// Get packet id
-    pub fn id(&self) -> u8 {
+    pub fn id(&self) -> i32 {
         self.id
     }
 
     /// Set packet id
-    pub fn set_id(&mut self, id: u8) {
+    pub fn set_id(&mut self, id: i32) {
         self.id = id;
     }
 
     /// Set packet cursor
-    pub fn set_cursor(&mut self, cursor: Cursor<Vec<u8>>) {
+    pub fn set_cursor(&mut self, cursor: Cursor<B>) {
         self.cursor = cursor;
     }
 
+    /// Set max length of String in bytes, see [`Limits`](crate::limits::Limits)
+    pub fn set_max_string_length(&mut self, max_string_length: usize) {
+        self.max_string_length = max_string_length;
+    }
+
+    /// Set whether typed packets decoded from this packet must have no bytes after body
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    /// Get whether typed packets decoded from this packet must have no bytes after body
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    /// Fail if packet is strict and has bytes left after typed packet is decoded
+    pub(crate) fn check_trailing_bytes(&self) -> Result<(), ProtocolError> {
+        if self.strict && !self.is_empty() {
+            return Err(ProtocolError::TrailingBytesError {
+                packet_id: self.id,
+                count: self.len(),
+            });
+        }
+        Ok(())
+    }
+
     /// Get cursor length
     pub fn len(&self) -> usize {
         self.cursor.get_ref().len() - self.cursor.position() as usize
@@ -84,39 +196,93 @@ impl Packet {
 
     /// Get cursor remaining bytes
     pub fn get_bytes(&self) -> &[u8] {
-        &self.cursor.get_ref()
+        self.cursor.get_ref()
+    }
+
+    /// Read `size` bytes without copying them
+    pub fn read_slice(&mut self, size: usize) -> Result<&[u8], ProtocolError> {
+        let remaining = self.len();
+        if size > remaining {
+            return Err(ProtocolError::DataRanOutError {
+                field: None,
+                packet_id: Some(self.id),
+                expected: size,
+                actual: remaining,
+            });
+        }
+        let position = self.cursor.position() as usize;
+        self.cursor.set_position((position + size) as u64);
+        Ok(&self.cursor.get_ref()[position..position + size])
     }
 
     /// Get mutable reference to cursor
-    pub fn get_mut(&mut self) -> &mut Cursor<Vec<u8>> {
+    pub fn get_mut(&mut self) -> &mut Cursor<B> {
         &mut self.cursor
     }
 
     /// Get immutable reference to cursor
-    pub fn get_ref(&self) -> &Cursor<Vec<u8>> {
+    pub fn get_ref(&self) -> &Cursor<B> {
         &self.cursor
     }
 
     /// Get inner cursor
-    pub fn into_inner(self) -> Cursor<Vec<u8>> {
+    pub fn into_inner(self) -> Cursor<B> {
         self.cursor
     }
 }
 
-impl Into<Cursor<Vec<u8>>> for Packet {
-    fn into(self) -> Cursor<Vec<u8>> {
-        self.cursor
+#[cfg(feature = "bytes")]
+impl Packet<bytes::BytesMut> {
+    /// Get packet data without copying it
+    pub fn into_bytes(self) -> bytes::Bytes {
+        self.cursor.into_inner().freeze()
     }
 }
 
-impl DataReader for Packet {
+/// Data is copied if it shares memory with other buffers
+#[cfg(feature = "bytes")]
+impl From<Packet<bytes::BytesMut>> for Packet {
+    fn from(packet: Packet<bytes::BytesMut>) -> Self {
+        let position = packet.cursor.position();
+        let mut cursor = Cursor::new(Vec::from(packet.cursor.into_inner()));
+        cursor.set_position(position);
+        Packet {
+            id: packet.id,
+            cursor,
+            max_string_length: packet.max_string_length,
+            strict: packet.strict,
+        }
+    }
+}
+
+impl<B: PacketData> From<Packet<B>> for Cursor<B> {
+    fn from(packet: Packet<B>) -> Self {
+        packet.cursor
+    }
+}
+
+impl<B: PacketData> DataReader for Packet<B> {
+    fn max_string_length(&self) -> usize {
+        self.max_string_length
+    }
+
     fn read_bytes(&mut self, size: usize) -> Result<Vec<u8>, ProtocolError> {
-        self.cursor.read_bytes(size)
+        self.read_slice(size).map(<[u8]>::to_vec)
     }
 }
 
-impl DataWriter for Packet {
+impl<B: PacketData> DataWriter for Packet<B> {
     fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), ProtocolError> {
-        self.cursor.write_bytes(bytes)
+        // same as writing to Cursor<Vec<u8>>, bytes after position are overwritten
+        let position = self.cursor.position() as usize;
+        let buffer = self.cursor.get_mut();
+        if buffer.len() < position {
+            buffer.resize_zeroed(position);
+        }
+        let overwrite = (buffer.len() - position).min(bytes.len());
+        buffer[position..position + overwrite].copy_from_slice(&bytes[..overwrite]);
+        buffer.append_slice(&bytes[overwrite..]);
+        self.cursor.set_position((position + bytes.len()) as u64);
+        Ok(())
     }
 }
@@ -0,0 +1,210 @@
+//! Configuration state packets
+
+use crate::{data::nbt::Nbt, packets::McPacket, DataReader, DataWriter, Packet, ProtocolError};
+
+/// Entry of registry, `data` can be `None` if client has it in known pack
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegistryEntry {
+    pub id: String,
+    pub data: Option<Nbt>,
+}
+
+/// Clientbound registry data, entries of one registry like `minecraft:dimension_type`
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegistryData {
+    pub registry_id: String,
+    pub entries: Vec<RegistryEntry>,
+}
+
+impl McPacket for RegistryData {
+    const ID: i32 = 0x07;
+
+    fn encode(&self, packet: &mut Packet) -> Result<(), ProtocolError> {
+        packet.write_string(&self.registry_id)?;
+        packet.write_prefixed_array(&self.entries, |packet, entry| {
+            packet.write_string(&entry.id)?;
+            packet.write_optional(entry.data.as_ref(), |packet, data| packet.write_nbt(data))
+        })
+    }
+
+    fn decode(packet: &mut Packet) -> Result<Self, ProtocolError> {
+        Ok(RegistryData {
+            registry_id: packet.read_string()?,
+            entries: packet.read_prefixed_array(|packet| {
+                Ok(RegistryEntry {
+                    id: packet.read_string()?,
+                    data: packet.read_optional(|packet| packet.read_nbt())?,
+                })
+            })?,
+        })
+    }
+}
+
+/// Clientbound feature flags, like `minecraft:vanilla`
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeatureFlags {
+    pub features: Vec<String>,
+}
+
+impl McPacket for FeatureFlags {
+    const ID: i32 = 0x0C;
+
+    fn encode(&self, packet: &mut Packet) -> Result<(), ProtocolError> {
+        packet.write_prefixed_array(&self.features, |packet, i| packet.write_string(i))
+    }
+
+    fn decode(packet: &mut Packet) -> Result<Self, ProtocolError> {
+        Ok(FeatureFlags {
+            features: packet.read_prefixed_array(|packet| packet.read_string())?,
+        })
+    }
+}
+
+/// Tag with ids of registry entries
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tag {
+    pub name: String,
+    pub entries: Vec<i32>,
+}
+
+/// Tags of one registry
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegistryTags {
+    pub registry: String,
+    pub tags: Vec<Tag>,
+}
+
+/// Clientbound update tags
+#[derive(Debug, Clone, PartialEq)]
+pub struct UpdateTags {
+    pub registries: Vec<RegistryTags>,
+}
+
+impl McPacket for UpdateTags {
+    const ID: i32 = 0x0D;
+
+    fn encode(&self, packet: &mut Packet) -> Result<(), ProtocolError> {
+        packet.write_prefixed_array(&self.registries, |packet, registry| {
+            packet.write_string(&registry.registry)?;
+            packet.write_prefixed_array(&registry.tags, |packet, tag| {
+                packet.write_string(&tag.name)?;
+                packet.write_prefixed_array(&tag.entries, |packet, i| packet.write_varint(*i))
+            })
+        })
+    }
+
+    fn decode(packet: &mut Packet) -> Result<Self, ProtocolError> {
+        Ok(UpdateTags {
+            registries: packet.read_prefixed_array(|packet| {
+                Ok(RegistryTags {
+                    registry: packet.read_string()?,
+                    tags: packet.read_prefixed_array(|packet| {
+                        Ok(Tag {
+                            name: packet.read_string()?,
+                            entries: packet.read_prefixed_array(|packet| packet.read_varint())?,
+                        })
+                    })?,
+                })
+            })?,
+        })
+    }
+}
+
+/// Data pack, like `minecraft:core` of version `1.21.4`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KnownPack {
+    pub namespace: String,
+    pub id: String,
+    pub version: String,
+}
+
+fn write_known_packs(packet: &mut Packet, packs: &[KnownPack]) -> Result<(), ProtocolError> {
+    packet.write_prefixed_array(packs, |packet, pack| {
+        packet.write_string(&pack.namespace)?;
+        packet.write_string(&pack.id)?;
+        packet.write_string(&pack.version)
+    })
+}
+
+fn read_known_packs(packet: &mut Packet) -> Result<Vec<KnownPack>, ProtocolError> {
+    packet.read_prefixed_array(|packet| {
+        Ok(KnownPack {
+            namespace: packet.read_string()?,
+            id: packet.read_string()?,
+            version: packet.read_string()?,
+        })
+    })
+}
+
+/// Clientbound known packs, packs that server has
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClientboundKnownPacks {
+    pub packs: Vec<KnownPack>,
+}
+
+impl McPacket for ClientboundKnownPacks {
+    const ID: i32 = 0x0E;
+
+    fn encode(&self, packet: &mut Packet) -> Result<(), ProtocolError> {
+        write_known_packs(packet, &self.packs)
+    }
+
+    fn decode(packet: &mut Packet) -> Result<Self, ProtocolError> {
+        Ok(ClientboundKnownPacks {
+            packs: read_known_packs(packet)?,
+        })
+    }
+}
+
+/// Serverbound known packs, packs from [`ClientboundKnownPacks`](ClientboundKnownPacks)
+/// that client has too
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerboundKnownPacks {
+    pub packs: Vec<KnownPack>,
+}
+
+impl McPacket for ServerboundKnownPacks {
+    const ID: i32 = 0x07;
+
+    fn encode(&self, packet: &mut Packet) -> Result<(), ProtocolError> {
+        write_known_packs(packet, &self.packs)
+    }
+
+    fn decode(packet: &mut Packet) -> Result<Self, ProtocolError> {
+        Ok(ServerboundKnownPacks {
+            packs: read_known_packs(packet)?,
+        })
+    }
+}
+
+/// Clientbound finish configuration
+#[derive(Debug, Clone, PartialEq)]
+pub struct FinishConfiguration;
+
+impl McPacket for FinishConfiguration {
+    const ID: i32 = 0x03;
+
+    fn encode(&self, _: &mut Packet) -> Result<(), ProtocolError> {
+        Ok(())
+    }
+
+    fn decode(_: &mut Packet) -> Result<Self, ProtocolError> {
+        Ok(FinishConfiguration)
+    }
+}
+
+/// Serverbound acknowledge finish configuration, switches connection to play state
+#[derive(Debug, Clone, PartialEq)]
+pub struct AcknowledgeFinishConfiguration;
+
+impl McPacket for AcknowledgeFinishConfiguration {
+    const ID: i32 = 0x03;
+
+    fn encode(&self, _: &mut Packet) -> Result<(), ProtocolError> {
+        Ok(())
+    }
+
+    fn decode(_: &mut Packet) -> Result<Self, ProtocolError> {
+        Ok(AcknowledgeFinishConfiguration)
+    }
+}
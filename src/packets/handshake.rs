@@ -0,0 +1,62 @@
+//! Handshaking state packets
+
+use crate::{packets::McPacket, DataReader, DataWriter, Packet, ProtocolError};
+
+/// State requested by client in [`Handshake`](Handshake)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NextState {
+    Status = 1,
+    Login = 2,
+    Transfer = 3,
+}
+
+impl TryFrom<i32> for NextState {
+    type Error = ProtocolError;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(NextState::Status),
+            2 => Ok(NextState::Login),
+            3 => Ok(NextState::Transfer),
+            i => Err(ProtocolError::InvalidValueError {
+                field: "Next State",
+                value: i as i64,
+            }),
+        }
+    }
+}
+
+impl From<NextState> for i32 {
+    fn from(value: NextState) -> Self {
+        value as i32
+    }
+}
+
+/// Serverbound handshake, first packet of every connection
+#[derive(Debug, Clone, PartialEq)]
+pub struct Handshake {
+    pub protocol_version: i32,
+    pub server_address: String,
+    pub server_port: u16,
+    pub next_state: NextState,
+}
+
+impl McPacket for Handshake {
+    const ID: i32 = 0x00;
+
+    fn encode(&self, packet: &mut Packet) -> Result<(), ProtocolError> {
+        packet.write_varint(self.protocol_version)?;
+        packet.write_string(&self.server_address)?;
+        packet.write_unsigned_short(self.server_port)?;
+        packet.write_varint_enum(self.next_state)
+    }
+
+    fn decode(packet: &mut Packet) -> Result<Self, ProtocolError> {
+        Ok(Handshake {
+            protocol_version: packet.read_varint()?,
+            server_address: packet.read_string()?,
+            server_port: packet.read_unsigned_short()?,
+            next_state: packet.read_varint_enum()?,
+        })
+    }
+}
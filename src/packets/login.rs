@@ -0,0 +1,242 @@
+//! Login state packets
+
+use crate::{packets::McPacket, DataReader, DataWriter, Packet, ProtocolError};
+use uuid::Uuid;
+
+fn read_byte_array(packet: &mut Packet) -> Result<Vec<u8>, ProtocolError> {
+    let size = packet.read_usize_varint()?;
+    packet.read_bytes(size)
+}
+
+fn write_byte_array(packet: &mut Packet, bytes: &[u8]) -> Result<(), ProtocolError> {
+    packet.write_usize_varint(bytes.len())?;
+    packet.write_bytes(bytes)
+}
+
+/// Clientbound disconnect during login
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoginDisconnect {
+    /// Reason text component as JSON
+    pub reason: String,
+}
+
+impl McPacket for LoginDisconnect {
+    const ID: i32 = 0x00;
+
+    fn encode(&self, packet: &mut Packet) -> Result<(), ProtocolError> {
+        packet.write_string(&self.reason)
+    }
+
+    fn decode(packet: &mut Packet) -> Result<Self, ProtocolError> {
+        Ok(LoginDisconnect {
+            reason: packet.read_string()?,
+        })
+    }
+}
+
+/// Clientbound encryption request
+#[derive(Debug, Clone, PartialEq)]
+pub struct EncryptionRequest {
+    pub server_id: String,
+    /// DER encoded RSA public key
+    pub public_key: Vec<u8>,
+    pub verify_token: Vec<u8>,
+    pub should_authenticate: bool,
+}
+
+impl McPacket for EncryptionRequest {
+    const ID: i32 = 0x01;
+
+    fn encode(&self, packet: &mut Packet) -> Result<(), ProtocolError> {
+        packet.write_string(&self.server_id)?;
+        write_byte_array(packet, &self.public_key)?;
+        write_byte_array(packet, &self.verify_token)?;
+        packet.write_boolean(self.should_authenticate)
+    }
+
+    fn decode(packet: &mut Packet) -> Result<Self, ProtocolError> {
+        Ok(EncryptionRequest {
+            server_id: packet.read_string()?,
+            public_key: read_byte_array(packet)?,
+            verify_token: read_byte_array(packet)?,
+            should_authenticate: packet.read_boolean()?,
+        })
+    }
+}
+
+/// Game profile property, like skin textures
+#[derive(Debug, Clone, PartialEq)]
+pub struct Property {
+    pub name: String,
+    pub value: String,
+    pub signature: Option<String>,
+}
+
+/// Clientbound login success
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoginSuccess {
+    pub uuid: Uuid,
+    pub username: String,
+    pub properties: Vec<Property>,
+}
+
+impl McPacket for LoginSuccess {
+    const ID: i32 = 0x02;
+
+    fn encode(&self, packet: &mut Packet) -> Result<(), ProtocolError> {
+        packet.write_uuid(&self.uuid)?;
+        packet.write_string(&self.username)?;
+        packet.write_prefixed_array(&self.properties, |packet, property| {
+            packet.write_string(&property.name)?;
+            packet.write_string(&property.value)?;
+            packet.write_optional(property.signature.as_ref(), |packet, signature| {
+                packet.write_string(signature)
+            })
+        })
+    }
+
+    fn decode(packet: &mut Packet) -> Result<Self, ProtocolError> {
+        Ok(LoginSuccess {
+            uuid: packet.read_uuid()?,
+            username: packet.read_string()?,
+            properties: packet.read_prefixed_array(|packet| {
+                Ok(Property {
+                    name: packet.read_string()?,
+                    value: packet.read_string()?,
+                    signature: packet.read_optional(|packet| packet.read_string())?,
+                })
+            })?,
+        })
+    }
+}
+
+/// Clientbound set compression, threshold below zero disables compression
+#[derive(Debug, Clone, PartialEq)]
+pub struct SetCompression {
+    pub threshold: i32,
+}
+
+impl McPacket for SetCompression {
+    const ID: i32 = 0x03;
+
+    fn encode(&self, packet: &mut Packet) -> Result<(), ProtocolError> {
+        packet.write_varint(self.threshold)
+    }
+
+    fn decode(packet: &mut Packet) -> Result<Self, ProtocolError> {
+        Ok(SetCompression {
+            threshold: packet.read_varint()?,
+        })
+    }
+}
+
+/// Clientbound login plugin request
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoginPluginRequest {
+    pub message_id: i32,
+    pub channel: String,
+    pub data: Vec<u8>,
+}
+
+impl McPacket for LoginPluginRequest {
+    const ID: i32 = 0x04;
+
+    fn encode(&self, packet: &mut Packet) -> Result<(), ProtocolError> {
+        packet.write_varint(self.message_id)?;
+        packet.write_string(&self.channel)?;
+        packet.write_bytes(&self.data)
+    }
+
+    fn decode(packet: &mut Packet) -> Result<Self, ProtocolError> {
+        Ok(LoginPluginRequest {
+            message_id: packet.read_varint()?,
+            channel: packet.read_string()?,
+            data: packet.read_bytes(packet.len())?,
+        })
+    }
+}
+
+/// Serverbound login start
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoginStart {
+    pub name: String,
+    pub uuid: Uuid,
+}
+
+impl McPacket for LoginStart {
+    const ID: i32 = 0x00;
+
+    fn encode(&self, packet: &mut Packet) -> Result<(), ProtocolError> {
+        packet.write_string(&self.name)?;
+        packet.write_uuid(&self.uuid)
+    }
+
+    fn decode(packet: &mut Packet) -> Result<Self, ProtocolError> {
+        Ok(LoginStart {
+            name: packet.read_string()?,
+            uuid: packet.read_uuid()?,
+        })
+    }
+}
+
+/// Serverbound encryption response, fields are encrypted with server public key
+#[derive(Debug, Clone, PartialEq)]
+pub struct EncryptionResponse {
+    pub shared_secret: Vec<u8>,
+    pub verify_token: Vec<u8>,
+}
+
+impl McPacket for EncryptionResponse {
+    const ID: i32 = 0x01;
+
+    fn encode(&self, packet: &mut Packet) -> Result<(), ProtocolError> {
+        write_byte_array(packet, &self.shared_secret)?;
+        write_byte_array(packet, &self.verify_token)
+    }
+
+    fn decode(packet: &mut Packet) -> Result<Self, ProtocolError> {
+        Ok(EncryptionResponse {
+            shared_secret: read_byte_array(packet)?,
+            verify_token: read_byte_array(packet)?,
+        })
+    }
+}
+
+/// Serverbound login plugin response, `data` is None if client doesn't understand request
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoginPluginResponse {
+    pub message_id: i32,
+    pub data: Option<Vec<u8>>,
+}
+
+impl McPacket for LoginPluginResponse {
+    const ID: i32 = 0x02;
+
+    fn encode(&self, packet: &mut Packet) -> Result<(), ProtocolError> {
+        packet.write_varint(self.message_id)?;
+        packet.write_optional(self.data.as_ref(), |packet, data| packet.write_bytes(data))
+    }
+
+    fn decode(packet: &mut Packet) -> Result<Self, ProtocolError> {
+        Ok(LoginPluginResponse {
+            message_id: packet.read_varint()?,
+            data: packet.read_optional(|packet| packet.read_bytes(packet.len()))?,
+        })
+    }
+}
+
+/// Serverbound login acknowledged, switches connection to configuration state
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoginAcknowledged;
+
+impl McPacket for LoginAcknowledged {
+    const ID: i32 = 0x03;
+
+    fn encode(&self, _: &mut Packet) -> Result<(), ProtocolError> {
+        Ok(())
+    }
+
+    fn decode(_: &mut Packet) -> Result<Self, ProtocolError> {
+        Ok(LoginAcknowledged)
+    }
+}
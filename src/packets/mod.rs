@@ -0,0 +1,135 @@
+//! Typed packets and [`McPacket`](McPacket) trait to convert them from and to [`Packet`](Packet)
+//!
+//! Packet ids and fields are for protocol [`PROTOCOL_VERSION`](PROTOCOL_VERSION)
+
+pub mod configuration;
+pub mod handshake;
+pub mod login;
+pub mod status;
+
+use crate::{
+    registry::{self, RegistryPacket},
+    state::{ConnectionState, Direction},
+    Packet, ProtocolError,
+};
+use configuration::*;
+use handshake::Handshake;
+use login::*;
+use status::*;
+
+/// Protocol version of typed packets (1.21.4)
+pub const PROTOCOL_VERSION: i32 = 769;
+
+/// Typed packet that can be converted from and to [`Packet`](Packet)
+pub trait McPacket: Sized {
+    /// Packet id
+    const ID: i32;
+
+    /// Write packet fields
+    fn encode(&self, packet: &mut Packet) -> Result<(), ProtocolError>;
+
+    /// Read packet fields
+    fn decode(packet: &mut Packet) -> Result<Self, ProtocolError>;
+
+    /// Build [`Packet`](Packet) from typed packet
+    fn to_packet(&self) -> Result<Packet, ProtocolError> {
+        Packet::build(Self::ID, |packet| self.encode(packet))
+    }
+
+    /// Read typed packet from [`Packet`](Packet), fails if packet has other id
+    fn from_packet(mut packet: Packet) -> Result<Self, ProtocolError> {
+        if packet.id() != Self::ID {
+            return Err(ProtocolError::UnexpectedPacketError {
+                expected: Self::ID,
+                actual: packet.id(),
+            });
+        }
+        let value = Self::decode(&mut packet).map_err(|e| e.with_packet_id(Self::ID))?;
+        packet.check_trailing_bytes()?;
+        Ok(value)
+    }
+}
+
+macro_rules! any_packet {
+    ($($state:ident $direction:ident $name:ident),* $(,)?) => {
+        /// Typed packet of any state and direction, see [`AnyPacket::decode`](AnyPacket::decode)
+        #[derive(Debug, Clone)]
+        pub enum AnyPacket {
+            $($name($name),)*
+            /// Packet that has no typed version
+            Unknown(Packet),
+        }
+
+        impl AnyPacket {
+            /// Decode typed packet by state, direction and packet id
+            ///
+            /// Packets without typed version are returned as [`AnyPacket::Unknown`](AnyPacket::Unknown)
+            pub fn decode(
+                state: ConnectionState,
+                direction: Direction,
+                packet: Packet,
+            ) -> Result<AnyPacket, ProtocolError> {
+                match (state, direction, packet.id()) {
+                    $(
+                        (ConnectionState::$state, Direction::$direction, $name::ID) => {
+                            Ok(AnyPacket::$name($name::from_packet(packet)?))
+                        }
+                    )*
+                    _ => Ok(AnyPacket::Unknown(packet)),
+                }
+            }
+
+            /// Decode typed packet with fields of protocol version, packet id is id of
+            /// [`PROTOCOL_VERSION`](PROTOCOL_VERSION)
+            pub(crate) fn decode_versioned(
+                protocol: i32,
+                state: ConnectionState,
+                direction: Direction,
+                packet: Packet,
+            ) -> Result<AnyPacket, ProtocolError> {
+                match (state, direction, packet.id()) {
+                    $(
+                        (ConnectionState::$state, Direction::$direction, $name::ID)
+                            if $name::supports_protocol(protocol) =>
+                        {
+                            Ok(AnyPacket::$name(registry::decode_fields(protocol, packet)?))
+                        }
+                    )*
+                    _ => Ok(AnyPacket::Unknown(packet)),
+                }
+            }
+
+            /// Build [`Packet`](Packet) from typed packet
+            pub fn to_packet(&self) -> Result<Packet, ProtocolError> {
+                match self {
+                    $(AnyPacket::$name(packet) => packet.to_packet(),)*
+                    AnyPacket::Unknown(packet) => Ok(packet.clone()),
+                }
+            }
+        }
+    };
+}
+
+any_packet! {
+    Handshake Serverbound Handshake,
+    Status Serverbound StatusRequest,
+    Status Serverbound PingRequest,
+    Status Clientbound StatusResponse,
+    Status Clientbound PongResponse,
+    Login Serverbound LoginStart,
+    Login Serverbound EncryptionResponse,
+    Login Serverbound LoginPluginResponse,
+    Login Serverbound LoginAcknowledged,
+    Login Clientbound LoginDisconnect,
+    Login Clientbound EncryptionRequest,
+    Login Clientbound LoginSuccess,
+    Login Clientbound SetCompression,
+    Login Clientbound LoginPluginRequest,
+    Configuration Serverbound ServerboundKnownPacks,
+    Configuration Serverbound AcknowledgeFinishConfiguration,
+    Configuration Clientbound RegistryData,
+    Configuration Clientbound FeatureFlags,
+    Configuration Clientbound UpdateTags,
+    Configuration Clientbound ClientboundKnownPacks,
+    Configuration Clientbound FinishConfiguration,
+}
@@ -0,0 +1,95 @@
+//! Status state packets
+
+use crate::{
+    packets::McPacket, status::ServerStatus, DataReader, DataWriter, Packet, ProtocolError,
+};
+
+/// Serverbound status request
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatusRequest;
+
+impl McPacket for StatusRequest {
+    const ID: i32 = 0x00;
+
+    fn encode(&self, _: &mut Packet) -> Result<(), ProtocolError> {
+        Ok(())
+    }
+
+    fn decode(_: &mut Packet) -> Result<Self, ProtocolError> {
+        Ok(StatusRequest)
+    }
+}
+
+/// Clientbound status response with status JSON
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatusResponse {
+    pub response: String,
+}
+
+impl StatusResponse {
+    /// Create packet from typed [`ServerStatus`](ServerStatus)
+    pub fn from_status(status: &ServerStatus) -> StatusResponse {
+        StatusResponse {
+            response: status.render(),
+        }
+    }
+
+    /// Parse status JSON to typed [`ServerStatus`](ServerStatus)
+    pub fn status(&self) -> Result<ServerStatus, ProtocolError> {
+        ServerStatus::parse(&self.response)
+    }
+}
+
+impl McPacket for StatusResponse {
+    const ID: i32 = 0x00;
+
+    fn encode(&self, packet: &mut Packet) -> Result<(), ProtocolError> {
+        packet.write_string(&self.response)
+    }
+
+    fn decode(packet: &mut Packet) -> Result<Self, ProtocolError> {
+        Ok(StatusResponse {
+            response: packet.read_string()?,
+        })
+    }
+}
+
+/// Serverbound ping request
+#[derive(Debug, Clone, PartialEq)]
+pub struct PingRequest {
+    pub payload: i64,
+}
+
+impl McPacket for PingRequest {
+    const ID: i32 = 0x01;
+
+    fn encode(&self, packet: &mut Packet) -> Result<(), ProtocolError> {
+        packet.write_long(self.payload)
+    }
+
+    fn decode(packet: &mut Packet) -> Result<Self, ProtocolError> {
+        Ok(PingRequest {
+            payload: packet.read_long()?,
+        })
+    }
+}
+
+/// Clientbound pong response, has the same payload as [`PingRequest`](PingRequest)
+#[derive(Debug, Clone, PartialEq)]
+pub struct PongResponse {
+    pub payload: i64,
+}
+
+impl McPacket for PongResponse {
+    const ID: i32 = 0x01;
+
+    fn encode(&self, packet: &mut Packet) -> Result<(), ProtocolError> {
+        packet.write_long(self.payload)
+    }
+
+    fn decode(packet: &mut Packet) -> Result<Self, ProtocolError> {
+        Ok(PongResponse {
+            payload: packet.read_long()?,
+        })
+    }
+}
@@ -0,0 +1,414 @@
+//! Registry of packet ids across protocol versions
+//!
+//! Maps logical packet kinds, like `login_success`, to ids of every protocol version and back.
+//! Registry is loaded from text file with lines `<protocol versions> <state> <direction> <name> <id>`,
+//! see `vanilla.txt` for example.
+//!
+//! Typed packets are encoded with fields of protocol version where they differ from
+//! [`PROTOCOL_VERSION`](crate::packets::PROTOCOL_VERSION), packets which fields are not known
+//! for protocol version fail with [`UnsupportedPacketError`](ProtocolError::UnsupportedPacketError)
+
+use crate::{
+    packets::{
+        configuration::*,
+        handshake::Handshake,
+        login::*,
+        status::*,
+        AnyPacket, McPacket,
+    },
+    state::{ConnectionState, Direction},
+    DataReader, DataWriter, Packet, ProtocolError,
+};
+use std::{collections::HashMap, fs, path::Path};
+
+/// First protocol version (1.20.5) with Known Packs, Registry Data of entries and
+/// Should Authenticate in Encryption Request
+pub(crate) const PROTOCOL_1_20_5: i32 = 766;
+
+/// Last protocol version (1.21.1) with Strict Error Handling in Login Success
+const PROTOCOL_1_21_1: i32 = 767;
+
+/// Bundled registry data of vanilla protocol versions 764 (1.20.2) to 769 (1.21.4)
+pub const VANILLA: &str = include_str!("vanilla.txt");
+
+/// Typed packet that has name in [`ProtocolRegistry`](ProtocolRegistry)
+pub trait RegistryPacket: McPacket {
+    /// State that packet is sent in
+    const STATE: ConnectionState;
+    /// Direction that packet is sent in
+    const DIRECTION: Direction;
+    /// Name of packet in registry
+    const NAME: &'static str;
+
+    /// Are packet fields of protocol version known, all versions by default
+    fn supports_protocol(protocol: i32) -> bool {
+        let _ = protocol;
+        true
+    }
+
+    /// Write packet fields of protocol version, same as [`encode`](McPacket::encode) by default
+    fn encode_versioned(&self, protocol: i32, packet: &mut Packet) -> Result<(), ProtocolError> {
+        let _ = protocol;
+        self.encode(packet)
+    }
+
+    /// Read packet fields of protocol version, same as [`decode`](McPacket::decode) by default
+    fn decode_versioned(protocol: i32, packet: &mut Packet) -> Result<Self, ProtocolError> {
+        let _ = protocol;
+        Self::decode(packet)
+    }
+}
+
+macro_rules! registry_packet {
+    ($($state:ident $direction:ident $name:ident $key:literal),* $(,)?) => {
+        $(
+            impl RegistryPacket for $name {
+                const STATE: ConnectionState = ConnectionState::$state;
+                const DIRECTION: Direction = Direction::$direction;
+                const NAME: &'static str = $key;
+            }
+        )*
+    };
+}
+
+registry_packet! {
+    Handshake Serverbound Handshake "handshake",
+    Status Serverbound StatusRequest "status_request",
+    Status Serverbound PingRequest "ping_request",
+    Status Clientbound StatusResponse "status_response",
+    Status Clientbound PongResponse "pong_response",
+    Login Serverbound LoginStart "login_start",
+    Login Serverbound EncryptionResponse "encryption_response",
+    Login Serverbound LoginPluginResponse "login_plugin_response",
+    Login Serverbound LoginAcknowledged "login_acknowledged",
+    Login Clientbound LoginDisconnect "login_disconnect",
+    Login Clientbound SetCompression "set_compression",
+    Login Clientbound LoginPluginRequest "login_plugin_request",
+    Configuration Serverbound ServerboundKnownPacks "known_packs",
+    Configuration Serverbound AcknowledgeFinishConfiguration "acknowledge_finish_configuration",
+    Configuration Clientbound FeatureFlags "feature_flags",
+    Configuration Clientbound UpdateTags "update_tags",
+    Configuration Clientbound ClientboundKnownPacks "known_packs",
+    Configuration Clientbound FinishConfiguration "finish_configuration",
+}
+
+impl RegistryPacket for EncryptionRequest {
+    const STATE: ConnectionState = ConnectionState::Login;
+    const DIRECTION: Direction = Direction::Clientbound;
+    const NAME: &'static str = "encryption_request";
+
+    fn encode_versioned(&self, protocol: i32, packet: &mut Packet) -> Result<(), ProtocolError> {
+        if protocol >= PROTOCOL_1_20_5 {
+            return self.encode(packet);
+        }
+        packet.write_string(&self.server_id)?;
+        packet.write_usize_varint(self.public_key.len())?;
+        packet.write_bytes(&self.public_key)?;
+        packet.write_usize_varint(self.verify_token.len())?;
+        packet.write_bytes(&self.verify_token)
+    }
+
+    /// Clients before 1.20.5 always authenticate, so `should_authenticate` is true
+    fn decode_versioned(protocol: i32, packet: &mut Packet) -> Result<Self, ProtocolError> {
+        if protocol >= PROTOCOL_1_20_5 {
+            return Self::decode(packet);
+        }
+        Ok(EncryptionRequest {
+            server_id: packet.read_string()?,
+            public_key: {
+                let size = packet.read_usize_varint()?;
+                packet.read_bytes(size)?
+            },
+            verify_token: {
+                let size = packet.read_usize_varint()?;
+                packet.read_bytes(size)?
+            },
+            should_authenticate: true,
+        })
+    }
+}
+
+/// Strict Error Handling of 1.20.5 and 1.21.1 is written as true and skipped when read
+impl RegistryPacket for LoginSuccess {
+    const STATE: ConnectionState = ConnectionState::Login;
+    const DIRECTION: Direction = Direction::Clientbound;
+    const NAME: &'static str = "login_success";
+
+    fn encode_versioned(&self, protocol: i32, packet: &mut Packet) -> Result<(), ProtocolError> {
+        self.encode(packet)?;
+        if (PROTOCOL_1_20_5..=PROTOCOL_1_21_1).contains(&protocol) {
+            packet.write_boolean(true)?;
+        }
+        Ok(())
+    }
+
+    fn decode_versioned(protocol: i32, packet: &mut Packet) -> Result<Self, ProtocolError> {
+        let value = Self::decode(packet)?;
+        if (PROTOCOL_1_20_5..=PROTOCOL_1_21_1).contains(&protocol) {
+            packet.read_boolean()?;
+        }
+        Ok(value)
+    }
+}
+
+/// Registry Data before 1.20.5 is one NBT of all registries, it is not supported
+impl RegistryPacket for RegistryData {
+    const STATE: ConnectionState = ConnectionState::Configuration;
+    const DIRECTION: Direction = Direction::Clientbound;
+    const NAME: &'static str = "registry_data";
+
+    fn supports_protocol(protocol: i32) -> bool {
+        protocol >= PROTOCOL_1_20_5
+    }
+}
+
+/// Read fields of typed packet of protocol version, packet id is not checked
+pub(crate) fn decode_fields<P: RegistryPacket>(
+    protocol: i32,
+    mut packet: Packet,
+) -> Result<P, ProtocolError> {
+    let id = packet.id();
+    let value = P::decode_versioned(protocol, &mut packet).map_err(|e| e.with_packet_id(id))?;
+    packet.check_trailing_bytes()?;
+    Ok(value)
+}
+
+type Key = (ConnectionState, Direction);
+
+/// Packet ids of one protocol version
+#[derive(Debug, Clone, Default)]
+struct VersionTable {
+    ids: HashMap<(Key, String), i32>,
+    names: HashMap<(Key, i32), String>,
+}
+
+/// Registry of packet ids across protocol versions
+#[derive(Debug, Clone, Default)]
+pub struct ProtocolRegistry {
+    versions: HashMap<i32, VersionTable>,
+}
+
+impl ProtocolRegistry {
+    /// Create empty registry
+    pub fn new() -> ProtocolRegistry {
+        ProtocolRegistry::default()
+    }
+
+    /// Create registry with bundled [`VANILLA`](VANILLA) data
+    pub fn vanilla() -> ProtocolRegistry {
+        ProtocolRegistry::parse(VANILLA).expect("bundled registry is valid")
+    }
+
+    /// Parse registry from text
+    pub fn parse(data: &str) -> Result<ProtocolRegistry, ProtocolError> {
+        let mut registry = ProtocolRegistry::new();
+        registry.load(data)?;
+        Ok(registry)
+    }
+
+    /// Read registry from file
+    pub fn read_file(path: impl AsRef<Path>) -> Result<ProtocolRegistry, ProtocolError> {
+        let data = fs::read_to_string(path).map_err(|e| ProtocolError::ReadError {
+            field: Some("Registry file"),
+            source: e,
+        })?;
+        ProtocolRegistry::parse(&data)
+    }
+
+    /// Load entries from text into registry, entries that already exist are replaced
+    pub fn load(&mut self, data: &str) -> Result<(), ProtocolError> {
+        for (i, line) in data.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let error = || ProtocolError::RegistryParseError { line: i + 1 };
+
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            let [versions, state, direction, name, id] = parts[..] else {
+                return Err(error());
+            };
+
+            let state = parse_state(state).ok_or_else(error)?;
+            let direction = parse_direction(direction).ok_or_else(error)?;
+            let id = parse_id(id).ok_or_else(error)?;
+
+            for protocol in parse_versions(versions).ok_or_else(error)? {
+                self.insert(protocol, state, direction, name, id);
+            }
+        }
+        Ok(())
+    }
+
+    /// Insert packet id of protocol version
+    pub fn insert(
+        &mut self,
+        protocol: i32,
+        state: ConnectionState,
+        direction: Direction,
+        name: &str,
+        id: i32,
+    ) {
+        let table = self.versions.entry(protocol).or_default();
+        if let Some(old) = table.ids.insert(((state, direction), name.to_string()), id) {
+            table.names.remove(&((state, direction), old));
+        }
+        table.names.insert(((state, direction), id), name.to_string());
+    }
+
+    /// Is protocol version in registry
+    pub fn supports(&self, protocol: i32) -> bool {
+        self.versions.contains_key(&protocol)
+    }
+
+    /// Get protocol versions in registry, sorted
+    pub fn versions(&self) -> Vec<i32> {
+        let mut versions: Vec<i32> = self.versions.keys().copied().collect();
+        versions.sort();
+        versions
+    }
+
+    /// Get packet id by name
+    pub fn id(
+        &self,
+        protocol: i32,
+        state: ConnectionState,
+        direction: Direction,
+        name: &str,
+    ) -> Option<i32> {
+        self.versions
+            .get(&protocol)?
+            .ids
+            .get(&((state, direction), name.to_string()))
+            .copied()
+    }
+
+    /// Get packet name by id
+    pub fn name(
+        &self,
+        protocol: i32,
+        state: ConnectionState,
+        direction: Direction,
+        id: i32,
+    ) -> Option<&str> {
+        self.versions
+            .get(&protocol)?
+            .names
+            .get(&((state, direction), id))
+            .map(String::as_str)
+    }
+
+    /// Get id of the same packet in other protocol version
+    pub fn translate(
+        &self,
+        from: i32,
+        to: i32,
+        state: ConnectionState,
+        direction: Direction,
+        id: i32,
+    ) -> Option<i32> {
+        let name = self.name(from, state, direction, id)?;
+        self.id(to, state, direction, name)
+    }
+
+    /// Get id of typed packet in protocol version, fails if packet has no id in this version
+    /// or its fields are not known for this version
+    pub fn packet_id<P: RegistryPacket>(&self, protocol: i32) -> Result<i32, ProtocolError> {
+        self.id(protocol, P::STATE, P::DIRECTION, P::NAME)
+            .filter(|_| P::supports_protocol(protocol))
+            .ok_or(ProtocolError::UnsupportedPacketError {
+                protocol,
+                name: P::NAME,
+            })
+    }
+
+    /// Build [`Packet`](Packet) from typed packet with id of protocol version
+    pub fn to_packet<P: RegistryPacket>(
+        &self,
+        protocol: i32,
+        packet: &P,
+    ) -> Result<Packet, ProtocolError> {
+        Packet::build(self.packet_id::<P>(protocol)?, |i| packet.encode_versioned(protocol, i))
+    }
+
+    /// Read typed packet from [`Packet`](Packet) with id of protocol version
+    pub fn from_packet<P: RegistryPacket>(
+        &self,
+        protocol: i32,
+        packet: Packet,
+    ) -> Result<P, ProtocolError> {
+        let id = self.packet_id::<P>(protocol)?;
+        if packet.id() != id {
+            return Err(ProtocolError::UnexpectedPacketError {
+                expected: id,
+                actual: packet.id(),
+            });
+        }
+        decode_fields(protocol, packet)
+    }
+
+    /// Decode typed packet with id and fields of protocol version,
+    /// see [`AnyPacket::decode`](AnyPacket::decode)
+    ///
+    /// Packets which fields are not known for protocol version are returned as
+    /// [`AnyPacket::Unknown`](AnyPacket::Unknown)
+    pub fn decode(
+        &self,
+        protocol: i32,
+        state: ConnectionState,
+        direction: Direction,
+        mut packet: Packet,
+    ) -> Result<AnyPacket, ProtocolError> {
+        let id = packet.id();
+        match self.translate(protocol, crate::packets::PROTOCOL_VERSION, state, direction, id) {
+            Some(translated) => {
+                packet.set_id(translated);
+                AnyPacket::decode_versioned(protocol, state, direction, packet).map(|mut i| {
+                    if let AnyPacket::Unknown(packet) = &mut i {
+                        packet.set_id(id);
+                    }
+                    i
+                })
+            }
+            None => Ok(AnyPacket::Unknown(packet)),
+        }
+    }
+}
+
+fn parse_state(value: &str) -> Option<ConnectionState> {
+    Some(match value {
+        "handshake" => ConnectionState::Handshake,
+        "status" => ConnectionState::Status,
+        "login" => ConnectionState::Login,
+        "configuration" => ConnectionState::Configuration,
+        "play" => ConnectionState::Play,
+        _ => return None,
+    })
+}
+
+fn parse_direction(value: &str) -> Option<Direction> {
+    Some(match value {
+        "clientbound" => Direction::Clientbound,
+        "serverbound" => Direction::Serverbound,
+        _ => return None,
+    })
+}
+
+fn parse_id(value: &str) -> Option<i32> {
+    match value.strip_prefix("0x") {
+        Some(hex) => i32::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
+
+fn parse_versions(value: &str) -> Option<Vec<i32>> {
+    let mut versions = Vec::new();
+    for part in value.split(',') {
+        match part.split_once('-') {
+            Some((start, end)) => versions.extend(start.parse::<i32>().ok()?..=end.parse().ok()?),
+            None => versions.push(part.parse().ok()?),
+        }
+    }
+    Some(versions)
+}
@@ -0,0 +1,273 @@
+//! Server that accepts connections and dispatches them to [`Handler`](Handler)
+//!
+//! Every connection is handled in its own thread. Server reads handshake, answers status
+//! requests and pings, and passes connections in Login state to [`Handler::login`](Handler::login)
+
+use crate::{
+    limits::Limits,
+    packets::{
+        handshake::{Handshake, NextState},
+        login::LoginDisconnect,
+        status::{PongResponse, StatusResponse},
+        AnyPacket,
+    },
+    status::{self, legacy::{self, LegacyPing, LegacyStatus}},
+    text::TextComponent,
+    MCConnTcp, ProtocolError,
+};
+use std::{
+    io,
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+/// Interval of checking for shutdown while there are no new connections
+const ACCEPT_POLL: Duration = Duration::from_millis(50);
+
+/// Default read and write timeout of connections, same as vanilla
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Callbacks of server, called from connection threads
+pub trait Handler: Send + Sync + 'static {
+    /// Status shown in server list
+    fn status(
+        &self,
+        conn: &MCConnTcp,
+        handshake: &Handshake,
+    ) -> Result<status::ServerStatus, ProtocolError>;
+
+    /// Status for legacy ping, `None` closes connection without answer
+    fn legacy_status(&self, _conn: &MCConnTcp, _ping: &LegacyPing) -> Option<LegacyStatus> {
+        None
+    }
+
+    /// Handle connection in Login state, disconnects client by default
+    ///
+    /// [`play`](Handler::play) is called after it if connection is still alive
+    fn login(&self, conn: &mut MCConnTcp, _handshake: &Handshake) -> Result<(), ProtocolError> {
+        conn.send(&LoginDisconnect {
+            reason: TextComponent::text("Login is not supported").to_json(),
+        })?;
+        conn.close();
+        Ok(())
+    }
+
+    /// Handle connection after login
+    fn play(&self, _conn: &mut MCConnTcp) -> Result<(), ProtocolError> {
+        Ok(())
+    }
+
+    /// Handle error of connection, ignored by default
+    ///
+    /// Errors of accepting connections are passed with address of server
+    fn error(&self, _addr: SocketAddr, _error: ProtocolError) {}
+}
+
+/// Handle to stop [`Server::run`](Server::run) from other thread
+#[derive(Debug, Clone, Default)]
+pub struct ShutdownHandle {
+    shutdown: Arc<AtomicBool>,
+}
+
+impl ShutdownHandle {
+    /// Stop accepting connections, running connections are not interrupted
+    pub fn shutdown(&self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+    }
+
+    /// Is shutdown requested
+    pub fn is_shutdown(&self) -> bool {
+        self.shutdown.load(Ordering::SeqCst)
+    }
+}
+
+/// Minecraft server listener
+pub struct Server<H: Handler> {
+    listener: TcpListener,
+    handler: Arc<H>,
+    max_connections: Option<usize>,
+    timeout: Option<Duration>,
+    limits: Limits,
+    connections: Arc<AtomicUsize>,
+    shutdown: ShutdownHandle,
+}
+
+impl<H: Handler> Server<H> {
+    /// Bind server to address
+    pub fn bind(addr: &str, handler: H) -> Result<Server<H>, ProtocolError> {
+        let listener = TcpListener::bind(crate::resolve_addr(addr)?)
+            .map_err(ProtocolError::StreamConnectError)?;
+
+        Ok(Server {
+            listener,
+            handler: Arc::new(handler),
+            max_connections: None,
+            timeout: Some(DEFAULT_TIMEOUT),
+            limits: Limits::default(),
+            connections: Arc::new(AtomicUsize::new(0)),
+            shutdown: ShutdownHandle::default(),
+        })
+    }
+
+    /// Set max count of connections at once, new connections over limit are closed
+    pub fn max_connections(mut self, max_connections: usize) -> Server<H> {
+        self.max_connections = Some(max_connections);
+        self
+    }
+
+    /// Set read and write timeout of connections, [`DEFAULT_TIMEOUT`](DEFAULT_TIMEOUT) by default
+    pub fn timeout(mut self, timeout: Duration) -> Server<H> {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Set limits of data received from connections
+    pub fn limits(mut self, limits: Limits) -> Server<H> {
+        self.limits = limits;
+        self
+    }
+
+    /// Get address that server is bound to
+    pub fn local_addr(&self) -> Result<SocketAddr, ProtocolError> {
+        self.listener
+            .local_addr()
+            .map_err(|e| ProtocolError::AddressParseError(Some(e)))
+    }
+
+    /// Get handler of server
+    pub fn handler(&self) -> &Arc<H> {
+        &self.handler
+    }
+
+    /// Get count of running connections
+    pub fn connections(&self) -> usize {
+        self.connections.load(Ordering::SeqCst)
+    }
+
+    /// Get handle to stop server
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        self.shutdown.clone()
+    }
+
+    /// Accept connections until shutdown, then wait for running connections to finish
+    ///
+    /// Errors of accepting connections, like too many open files, are passed to
+    /// [`Handler::error`](Handler::error) and server keeps accepting
+    pub fn run(&self) -> Result<(), ProtocolError> {
+        self.listener
+            .set_nonblocking(true)
+            .map_err(ProtocolError::StreamConnectError)?;
+        let local_addr = self.local_addr()?;
+
+        let mut threads: Vec<JoinHandle<()>> = Vec::new();
+
+        while !self.shutdown.is_shutdown() {
+            let (stream, addr) = match self.listener.accept() {
+                Ok(i) => i,
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    thread::sleep(ACCEPT_POLL);
+                    continue;
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    self.handler.error(local_addr, ProtocolError::StreamConnectError(e));
+                    thread::sleep(ACCEPT_POLL);
+                    continue;
+                }
+            };
+
+            threads.retain(|i| !i.is_finished());
+
+            if self
+                .max_connections
+                .is_some_and(|i| self.connections.load(Ordering::SeqCst) >= i)
+            {
+                drop(stream);
+                continue;
+            }
+
+            self.connections.fetch_add(1, Ordering::SeqCst);
+            let handler = self.handler.clone();
+            let connections = self.connections.clone();
+            let timeout = self.timeout;
+            let limits = self.limits;
+
+            threads.push(thread::spawn(move || {
+                if let Err(e) = handle_connection(&*handler, stream, timeout, limits) {
+                    handler.error(addr, e);
+                }
+                connections.fetch_sub(1, Ordering::SeqCst);
+            }));
+        }
+
+        for thread in threads {
+            let _ = thread.join();
+        }
+
+        Ok(())
+    }
+}
+
+fn handle_connection<H: Handler>(
+    handler: &H,
+    stream: TcpStream,
+    timeout: Option<Duration>,
+    limits: Limits,
+) -> Result<(), ProtocolError> {
+    stream
+        .set_nonblocking(false)
+        .and_then(|_| stream.set_read_timeout(timeout))
+        .and_then(|_| stream.set_write_timeout(timeout))
+        .map_err(ProtocolError::StreamConnectError)?;
+
+    let mut conn = MCConnTcp::new(stream);
+    conn.set_limits(limits);
+
+    if legacy::is_legacy_ping(conn.get_ref())? {
+        let ping = legacy::read_legacy_ping(conn.get_mut())?;
+        if let Some(status) = handler.legacy_status(&conn, &ping) {
+            legacy::write_legacy_status(conn.get_mut(), &ping, &status)?;
+        }
+        conn.close();
+        return Ok(());
+    }
+
+    let handshake = conn.recv::<Handshake>()?;
+
+    match handshake.next_state {
+        NextState::Status => {
+            let mut answered = false;
+            loop {
+                // client may close connection after status without ping
+                match conn.recv_any() {
+                    // only one status request is answered, same as vanilla
+                    Ok(AnyPacket::StatusRequest(_)) if !answered => {
+                        let status = handler.status(&conn, &handshake)?;
+                        conn.send(&StatusResponse::from_status(&status))?;
+                        answered = true;
+                    }
+                    Ok(AnyPacket::PingRequest(ping)) => {
+                        conn.send(&PongResponse { payload: ping.payload })?;
+                        break;
+                    }
+                    Ok(_) | Err(ProtocolError::ConnectionClosedError) => break,
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+        NextState::Login | NextState::Transfer => {
+            handler.login(&mut conn, &handshake)?;
+            if conn.is_alive() {
+                handler.play(&mut conn)?;
+            }
+        }
+    }
+
+    conn.close();
+    Ok(())
+}
@@ -0,0 +1,232 @@
+//! Protocol state machine, tracks which state connection is in
+//!
+//! Transitions are taken from packets that are read and written:
+//! - Handshake -> Status / Login, by `next_state` of [`Handshake`](crate::packets::handshake::Handshake)
+//! - Login -> Configuration, by Login Acknowledged (reply to Login Success)
+//! - Configuration -> Play, by Acknowledge Finish Configuration (reply to Finish Configuration)
+//! - Play -> Configuration, by Acknowledge Configuration (reply to Start Configuration)
+//!
+//! Ids of these packets are looked up in [`ProtocolRegistry`](ProtocolRegistry) if connection has one,
+//! otherwise ids of [`PROTOCOL_VERSION`](crate::packets::PROTOCOL_VERSION) are used
+
+use crate::{
+    packets::{handshake::{Handshake, NextState}, AnyPacket, McPacket},
+    registry::{ProtocolRegistry, RegistryPacket},
+    packet::PacketData,
+    Packet, ProtocolError,
+};
+use std::sync::Arc;
+
+#[cfg(feature = "atomic_clone")]
+use std::sync::Mutex;
+
+/// Serverbound Acknowledge Configuration id in Play state
+pub const ACKNOWLEDGE_CONFIGURATION_ID: i32 = 0x0E;
+
+/// Serverbound Login Acknowledged id in Login state
+pub const LOGIN_ACKNOWLEDGED_ID: i32 = 0x03;
+
+/// Serverbound Acknowledge Finish Configuration id in Configuration state
+pub const ACKNOWLEDGE_FINISH_CONFIGURATION_ID: i32 = 0x03;
+
+/// Protocol state of connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ConnectionState {
+    #[default]
+    Handshake,
+    Status,
+    Login,
+    Configuration,
+    Play,
+}
+
+impl ConnectionState {
+    /// Get state after serverbound packet with `id` other than handshake, `None` if state doesn't change
+    pub fn next(self, id: i32) -> Option<ConnectionState> {
+        match (self, id) {
+            (ConnectionState::Login, LOGIN_ACKNOWLEDGED_ID) => Some(ConnectionState::Configuration),
+            (ConnectionState::Configuration, ACKNOWLEDGE_FINISH_CONFIGURATION_ID) => {
+                Some(ConnectionState::Play)
+            }
+            (ConnectionState::Play, ACKNOWLEDGE_CONFIGURATION_ID) => {
+                Some(ConnectionState::Configuration)
+            }
+            _ => None,
+        }
+    }
+
+    /// Get state after serverbound packet with `name` in registry, `None` if state doesn't change
+    pub fn next_by_name(self, name: &str) -> Option<ConnectionState> {
+        match (self, name) {
+            (ConnectionState::Login, "login_acknowledged") => Some(ConnectionState::Configuration),
+            (ConnectionState::Configuration, "acknowledge_finish_configuration") => {
+                Some(ConnectionState::Play)
+            }
+            (ConnectionState::Play, "acknowledge_configuration") => {
+                Some(ConnectionState::Configuration)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Direction of packet
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    /// Sent by server to client
+    Clientbound,
+    /// Sent by client to server
+    Serverbound,
+}
+
+/// Side of connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Side {
+    Client,
+    Server,
+}
+
+impl Side {
+    /// Direction of packets read by this side
+    pub fn inbound(self) -> Direction {
+        match self {
+            Side::Client => Direction::Clientbound,
+            Side::Server => Direction::Serverbound,
+        }
+    }
+
+    /// Direction of packets written by this side
+    pub fn outbound(self) -> Direction {
+        match self {
+            Side::Client => Direction::Serverbound,
+            Side::Server => Direction::Clientbound,
+        }
+    }
+}
+
+/// State, side and protocol version of connection
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Tracker {
+    pub state: ConnectionState,
+    /// Unknown until first packet, client writes handshake and server reads it
+    pub side: Option<Side>,
+    /// Taken from handshake
+    pub protocol_version: Option<i32>,
+    pub registry: Option<Arc<ProtocolRegistry>>,
+}
+
+impl Tracker {
+    /// Direction of packets read from connection, server if side is not known yet
+    pub fn inbound(&self) -> Direction {
+        self.side.unwrap_or(Side::Server).inbound()
+    }
+
+    /// Registry and protocol version, if registry has this version
+    fn versioned(&self) -> Option<(&ProtocolRegistry, i32)> {
+        let registry = self.registry.as_deref()?;
+        let protocol = self.protocol_version?;
+        registry.supports(protocol).then_some((registry, protocol))
+    }
+
+    /// Decode typed packet read in current state
+    pub fn decode_any(&self, direction: Direction, packet: Packet) -> Result<AnyPacket, ProtocolError> {
+        match self.versioned() {
+            Some((registry, protocol)) => registry.decode(protocol, self.state, direction, packet),
+            None => AnyPacket::decode(self.state, direction, packet),
+        }
+    }
+
+    /// Build [`Packet`](Packet) from typed packet with id of protocol version
+    pub fn encode<P: RegistryPacket>(&self, packet: &P) -> Result<Packet, ProtocolError> {
+        match self.versioned() {
+            Some((registry, protocol)) => registry.to_packet(protocol, packet),
+            None => packet.to_packet(),
+        }
+    }
+
+    /// Read typed packet from [`Packet`](Packet) with id of protocol version
+    pub fn decode<P: RegistryPacket>(&self, packet: Packet) -> Result<P, ProtocolError> {
+        match self.versioned() {
+            Some((registry, protocol)) => registry.from_packet(protocol, packet),
+            None => P::from_packet(packet),
+        }
+    }
+
+    /// Update state after packet is read (`outbound` is false) or written (`outbound` is true)
+    pub fn update<B: PacketData>(&mut self, outbound: bool, packet: &Packet<B>) {
+        if self.side.is_none() && self.state == ConnectionState::Handshake {
+            self.side = Some(if outbound { Side::Client } else { Side::Server });
+        }
+
+        let Some(side) = self.side else {
+            return;
+        };
+
+        let direction = if outbound { side.outbound() } else { side.inbound() };
+        if direction != Direction::Serverbound {
+            return;
+        }
+
+        if self.state == ConnectionState::Handshake {
+            if packet.id() != Handshake::ID {
+                return;
+            }
+            let mut packet = Packet::from_bytes(packet.id(), packet.get_bytes());
+            if let Ok(handshake) = Handshake::decode(&mut packet) {
+                self.protocol_version = Some(handshake.protocol_version);
+                self.state = match handshake.next_state {
+                    NextState::Status => ConnectionState::Status,
+                    NextState::Login | NextState::Transfer => ConnectionState::Login,
+                };
+            }
+            return;
+        }
+
+        let next = match self.versioned() {
+            Some((registry, protocol)) => registry
+                .name(protocol, self.state, direction, packet.id())
+                .and_then(|name| self.state.next_by_name(name)),
+            None => self.state.next(packet.id()),
+        };
+
+        if let Some(state) = next {
+            self.state = state;
+        }
+    }
+}
+
+/// Tracker shared between clones of connection
+#[cfg(feature = "atomic_clone")]
+pub(crate) type TrackerCell = Arc<Mutex<Tracker>>;
+
+/// Tracker owned by connection
+#[cfg(not(feature = "atomic_clone"))]
+pub(crate) type TrackerCell = Tracker;
+
+pub(crate) fn new_cell() -> TrackerCell {
+    TrackerCell::default()
+}
+
+/// Run closure with tracker of connection
+pub(crate) fn with_cell<R>(cell: &mut TrackerCell, f: impl FnOnce(&mut Tracker) -> R) -> R {
+    #[cfg(feature = "atomic_clone")]
+    {
+        f(&mut cell.lock().unwrap())
+    }
+    #[cfg(not(feature = "atomic_clone"))]
+    {
+        f(cell)
+    }
+}
+
+/// Read value from tracker of connection
+pub(crate) fn read_cell<R>(cell: &TrackerCell, f: impl FnOnce(&Tracker) -> R) -> R {
+    #[cfg(feature = "atomic_clone")]
+    {
+        f(&cell.lock().unwrap())
+    }
+    #[cfg(not(feature = "atomic_clone"))]
+    {
+        f(cell)
+    }
+}
@@ -0,0 +1,305 @@
+//! Legacy server list ping (`0xFE`), used by clients before 1.7 and by many scanners
+//!
+//! Legacy ping is not framed like other packets, so it must be detected on accepted stream
+//! with [`is_legacy_ping`](is_legacy_ping) before first
+//! [`read_packet`](crate::MinecraftConnection::read_packet).
+//! Server answers with kick packet `0xFF` that contains status as UTF-16BE string
+//! and closes connection
+
+use super::{connect, ServerStatus, DEFAULT_TIMEOUT};
+use crate::{limits::check_length, ProtocolError};
+use std::{
+    io::{self, Read, Write},
+    net::{Shutdown, TcpStream},
+    time::Duration,
+};
+
+/// First byte of legacy ping
+pub const PING_ID: u8 = 0xFE;
+
+/// Id of kick packet that contains status
+pub const KICK_ID: u8 = 0xFF;
+
+/// Time to wait for next byte of ping, older clients send only part of it
+pub const PING_WAIT: Duration = Duration::from_millis(100);
+
+/// Protocol version sent by [`ping`](ping), 1.6.4
+const CLIENT_PROTOCOL: u8 = 78;
+
+/// Channel of 1.6 ping
+const PING_HOST_CHANNEL: &str = "MC|PingHost";
+
+/// Legacy ping sent by client
+#[derive(Debug, Clone, PartialEq)]
+pub enum LegacyPing {
+    /// Beta 1.8 to 1.3, only `0xFE`
+    Beta,
+    /// 1.4 to 1.5, `0xFE 0x01`
+    V1_4,
+    /// 1.6, `0xFE 0x01 0xFA` and `MC|PingHost` plugin message
+    V1_6 {
+        protocol: u8,
+        host: String,
+        port: i32,
+    },
+}
+
+/// Status sent in legacy kick packet
+#[derive(Debug, Clone, PartialEq)]
+pub struct LegacyStatus {
+    /// Protocol version, `0` if server answered beta ping
+    pub protocol: i32,
+    /// Version name, empty if server answered beta ping
+    pub version: String,
+    /// Message of the day with legacy formatting
+    pub motd: String,
+    pub online: i32,
+    pub max: i32,
+}
+
+impl LegacyStatus {
+    /// Convert server status, description is converted to legacy formatting
+    pub fn from_status(status: &ServerStatus) -> LegacyStatus {
+        let (online, max) = status
+            .players
+            .as_ref()
+            .map(|i| (i.online, i.max))
+            .unwrap_or_default();
+
+        LegacyStatus {
+            protocol: status.version.protocol,
+            version: status.version.name.clone(),
+            motd: status.description.to_legacy(),
+            online,
+            max,
+        }
+    }
+
+    /// Text of kick packet, beta clients get message without version and formatting
+    pub fn render(&self, ping: &LegacyPing) -> String {
+        match ping {
+            LegacyPing::Beta => {
+                format!("{}§{}§{}", strip_formatting(&self.motd), self.online, self.max)
+            }
+            _ => format!(
+                "§1\0{}\0{}\0{}\0{}\0{}",
+                self.protocol, self.version, self.motd, self.online, self.max
+            ),
+        }
+    }
+
+    /// Parse text of kick packet, both beta and 1.4+ formats
+    pub fn parse(text: &str) -> Result<LegacyStatus, ProtocolError> {
+        let number =
+            |value: &str, field| value.parse().map_err(|_| ProtocolError::StatusError(field));
+
+        if let Some(text) = text.strip_prefix("§1\0") {
+            let parts: Vec<&str> = text.split('\0').collect();
+            let [protocol, version, motd, online, max] = parts[..] else {
+                return Err(ProtocolError::StatusError("legacy status"));
+            };
+            return Ok(LegacyStatus {
+                protocol: number(protocol, "protocol")?,
+                version: version.to_string(),
+                motd: motd.to_string(),
+                online: number(online, "online")?,
+                max: number(max, "max")?,
+            });
+        }
+
+        let mut parts = text.rsplitn(3, '§');
+        let (Some(max), Some(online), Some(motd)) = (parts.next(), parts.next(), parts.next())
+        else {
+            return Err(ProtocolError::StatusError("legacy status"));
+        };
+        Ok(LegacyStatus {
+            protocol: 0,
+            version: String::new(),
+            motd: motd.to_string(),
+            online: number(online, "online")?,
+            max: number(max, "max")?,
+        })
+    }
+}
+
+/// Remove `§` formatting codes from text
+fn strip_formatting(text: &str) -> String {
+    let mut result = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '§' {
+            chars.next();
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+/// Check that client sent legacy ping, stream is not consumed
+///
+/// Blocks until client sends first byte or read timeout of stream is reached
+pub fn is_legacy_ping(stream: &TcpStream) -> Result<bool, ProtocolError> {
+    let mut buf = [0; 1];
+    match stream.peek(&mut buf) {
+        Ok(0) => Err(ProtocolError::ConnectionClosedError),
+        Ok(_) => Ok(buf[0] == PING_ID),
+        Err(e) => Err(ProtocolError::ReadError {
+            field: Some("Legacy Ping"),
+            source: e,
+        }),
+    }
+}
+
+/// Read legacy ping from stream
+///
+/// Version is detected by bytes that client sends within [`PING_WAIT`](PING_WAIT)
+pub fn read_legacy_ping(stream: &mut TcpStream) -> Result<LegacyPing, ProtocolError> {
+    let id = read_array::<1>(stream)?[0];
+    if id != PING_ID {
+        return Err(ProtocolError::UnexpectedPacketError {
+            expected: PING_ID as i32,
+            actual: id as i32,
+        });
+    }
+
+    Ok(match try_read_byte(stream)? {
+        Some(0x01) => match try_read_byte(stream)? {
+            Some(0xFA) => read_ping_host(stream)?,
+            _ => LegacyPing::V1_4,
+        },
+        _ => LegacyPing::Beta,
+    })
+}
+
+/// Read `MC|PingHost` plugin message after `0xFE 0x01 0xFA`
+fn read_ping_host(stream: &mut impl Read) -> Result<LegacyPing, ProtocolError> {
+    let channel = read_string(stream)?;
+    if channel != PING_HOST_CHANNEL {
+        return Err(ProtocolError::StatusError("legacy ping channel"));
+    }
+    read_short(stream)?;
+
+    let protocol = read_array::<1>(stream)?[0];
+    let host = read_string(stream)?;
+    let port = i32::from_be_bytes(read_array(stream)?);
+
+    Ok(LegacyPing::V1_6 { protocol, host, port })
+}
+
+/// Write kick packet with status, connection must be closed after it
+///
+/// Fails with [`LengthLimitError`](ProtocolError::LengthLimitError) if status is longer than
+/// 65535 UTF-16 units
+pub fn write_legacy_status(
+    writer: &mut impl Write,
+    ping: &LegacyPing,
+    status: &LegacyStatus,
+) -> Result<(), ProtocolError> {
+    let mut data = vec![KICK_ID];
+    write_string(&mut data, &status.render(ping))?;
+    writer
+        .write_all(&data)
+        .and_then(|_| writer.flush())
+        .map_err(ProtocolError::WriteError)
+}
+
+/// Get status of server with legacy ping, see [`ping_timeout`](ping_timeout)
+pub fn ping(addr: &str) -> Result<LegacyStatus, ProtocolError> {
+    ping_timeout(addr, DEFAULT_TIMEOUT)
+}
+
+/// Get status of server with 1.6 legacy ping
+///
+/// `addr` is `host` or `host:port`, default port is 25565.
+/// `timeout` is applied to connecting and to every read and write
+pub fn ping_timeout(addr: &str, timeout: Duration) -> Result<LegacyStatus, ProtocolError> {
+    let (mut stream, host, port) = connect(addr, timeout)?;
+
+    let mut data = Vec::new();
+    write_string(&mut data, host)?;
+    data.extend_from_slice(&(port as i32).to_be_bytes());
+    check_length("Legacy Ping Data", data.len() + 1, u16::MAX as usize)?;
+
+    let mut ping = vec![PING_ID, 0x01, 0xFA];
+    write_string(&mut ping, PING_HOST_CHANNEL)?;
+    ping.extend_from_slice(&(data.len() as u16 + 1).to_be_bytes());
+    ping.push(CLIENT_PROTOCOL);
+    ping.extend_from_slice(&data);
+
+    stream.write_all(&ping).map_err(ProtocolError::WriteError)?;
+
+    let id = read_array::<1>(&mut stream)?[0];
+    if id != KICK_ID {
+        return Err(ProtocolError::UnexpectedPacketError {
+            expected: KICK_ID as i32,
+            actual: id as i32,
+        });
+    }
+    let text = read_string(&mut stream)?;
+
+    let _ = stream.shutdown(Shutdown::Both);
+
+    LegacyStatus::parse(&text)
+}
+
+/// Read byte if client sends it within [`PING_WAIT`](PING_WAIT), previous read timeout is kept
+fn try_read_byte(stream: &mut TcpStream) -> Result<Option<u8>, ProtocolError> {
+    let error = |e| ProtocolError::ReadError {
+        field: Some("Legacy Ping"),
+        source: e,
+    };
+
+    let timeout = stream.read_timeout().map_err(error)?;
+    stream.set_read_timeout(Some(PING_WAIT)).map_err(error)?;
+    let mut buf = [0; 1];
+    let result = match stream.read(&mut buf) {
+        Ok(0) => Ok(None),
+        Ok(_) => Ok(Some(buf[0])),
+        Err(e) if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => {
+            Ok(None)
+        }
+        Err(e) => Err(error(e)),
+    };
+    stream.set_read_timeout(timeout).map_err(error)?;
+    result
+}
+
+fn read_array<const N: usize>(reader: &mut impl Read) -> Result<[u8; N], ProtocolError> {
+    let mut buf = [0; N];
+    match reader.read_exact(&mut buf) {
+        Ok(()) => Ok(buf),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+            Err(ProtocolError::ConnectionClosedError)
+        }
+        Err(e) => Err(ProtocolError::ReadError {
+            field: Some("Legacy Ping"),
+            source: e,
+        }),
+    }
+}
+
+fn read_short(reader: &mut impl Read) -> Result<u16, ProtocolError> {
+    Ok(u16::from_be_bytes(read_array(reader)?))
+}
+
+/// Read string as length in UTF-16 units and UTF-16BE data
+fn read_string(reader: &mut impl Read) -> Result<String, ProtocolError> {
+    let size = read_short(reader)?;
+    let mut units = Vec::with_capacity(size as usize);
+    for _ in 0..size {
+        units.push(read_short(reader)?);
+    }
+    Ok(String::from_utf16_lossy(&units))
+}
+
+/// Write string as length in UTF-16 units and UTF-16BE data
+fn write_string(data: &mut Vec<u8>, text: &str) -> Result<(), ProtocolError> {
+    let units: Vec<u16> = text.encode_utf16().collect();
+    check_length("Legacy String", units.len(), u16::MAX as usize)?;
+    data.extend_from_slice(&(units.len() as u16).to_be_bytes());
+    for unit in units {
+        data.extend_from_slice(&unit.to_be_bytes());
+    }
+    Ok(())
+}
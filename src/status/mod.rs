@@ -0,0 +1,299 @@
+//! Server list ping, server status JSON model
+
+pub mod legacy;
+
+use crate::{
+    packets::{
+        handshake::{Handshake, NextState},
+        status::{PingRequest, PongResponse, StatusRequest, StatusResponse},
+    },
+    resolve_addr,
+    text::TextComponent,
+    MCConnTcp, ProtocolError,
+};
+use serde_json::{Map, Value};
+use std::{
+    net::TcpStream,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use uuid::Uuid;
+
+/// Timeout of [`ping`](ping)
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Default port of Minecraft server
+pub const DEFAULT_PORT: u16 = 25565;
+
+/// Version of server
+#[derive(Debug, Clone, PartialEq)]
+pub struct Version {
+    /// Version name, like `1.21.4`
+    pub name: String,
+    /// Protocol version, client shows version as incompatible if it doesn't match
+    pub protocol: i32,
+}
+
+/// Player shown in player list on hover, `id` is nil if server sent invalid id
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlayerSample {
+    pub name: String,
+    pub id: Uuid,
+}
+
+/// Player counts of server
+#[derive(Debug, Clone, PartialEq)]
+pub struct Players {
+    pub max: i32,
+    pub online: i32,
+    pub sample: Vec<PlayerSample>,
+}
+
+/// Server status, JSON that server sends in status response
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerStatus {
+    pub version: Version,
+    /// `None` shows `???` instead of player count
+    pub players: Option<Players>,
+    /// Message of the day
+    pub description: TextComponent,
+    /// PNG image 64x64 as data URI, like `data:image/png;base64,...`
+    pub favicon: Option<String>,
+    pub enforces_secure_chat: bool,
+    /// Other fields, like `forgeData` of modded servers
+    pub extra: Map<String, Value>,
+}
+
+impl ServerStatus {
+    /// Create server status with version, player counts and description
+    pub fn new(
+        version: Version,
+        players: Option<Players>,
+        description: impl Into<TextComponent>,
+    ) -> ServerStatus {
+        ServerStatus {
+            version,
+            players,
+            description: description.into(),
+            favicon: None,
+            enforces_secure_chat: false,
+            extra: Map::new(),
+        }
+    }
+
+    /// Parse server status from JSON
+    pub fn parse(json: &str) -> Result<ServerStatus, ProtocolError> {
+        let value: Value = serde_json::from_str(json).map_err(ProtocolError::JsonError)?;
+        ServerStatus::from_value(&value)
+    }
+
+    /// Render server status to JSON
+    pub fn render(&self) -> String {
+        self.to_value().to_string()
+    }
+
+    /// Read server status from JSON value
+    pub fn from_value(value: &Value) -> Result<ServerStatus, ProtocolError> {
+        let Value::Object(map) = value else {
+            return Err(ProtocolError::StatusError("status"));
+        };
+        let mut extra = map.clone();
+
+        let version = extra.remove("version").ok_or(ProtocolError::StatusError("version"))?;
+        let version = Version {
+            name: get_str(&version, "name")?.to_string(),
+            protocol: get_i32(&version, "protocol")?,
+        };
+
+        let players = match extra.remove("players") {
+            Some(Value::Null) | None => None,
+            Some(players) => Some(Players {
+                max: get_i32(&players, "max")?,
+                online: get_i32(&players, "online")?,
+                sample: match players.get("sample") {
+                    // servers often put fake ids in sample, so they are kept with nil id
+                    Some(Value::Array(list)) => list
+                        .iter()
+                        .filter_map(|i| {
+                            Some(PlayerSample {
+                                name: i.get("name")?.as_str()?.to_string(),
+                                id: i
+                                    .get("id")
+                                    .and_then(Value::as_str)
+                                    .and_then(|id| Uuid::parse_str(id).ok())
+                                    .unwrap_or_default(),
+                            })
+                        })
+                        .collect(),
+                    _ => Vec::new(),
+                },
+            }),
+        };
+
+        let description = match extra.remove("description") {
+            Some(description) => TextComponent::from_value(&description)?,
+            None => TextComponent::default(),
+        };
+
+        let favicon = match extra.remove("favicon") {
+            Some(Value::String(favicon)) => Some(favicon),
+            Some(Value::Null) | None => None,
+            Some(_) => return Err(ProtocolError::StatusError("favicon")),
+        };
+
+        let enforces_secure_chat = match extra.remove("enforcesSecureChat") {
+            Some(Value::Bool(i)) => i,
+            Some(Value::Null) | None => false,
+            Some(_) => return Err(ProtocolError::StatusError("enforcesSecureChat")),
+        };
+
+        Ok(ServerStatus {
+            version,
+            players,
+            description,
+            favicon,
+            enforces_secure_chat,
+            extra,
+        })
+    }
+
+    /// Convert server status to JSON value
+    pub fn to_value(&self) -> Value {
+        let mut map = self.extra.clone();
+
+        let mut version = Map::new();
+        version.insert("name".to_string(), self.version.name.clone().into());
+        version.insert("protocol".to_string(), self.version.protocol.into());
+        map.insert("version".to_string(), version.into());
+
+        if let Some(players) = &self.players {
+            let sample: Vec<Value> = players
+                .sample
+                .iter()
+                .map(|i| {
+                    let mut player = Map::new();
+                    player.insert("name".to_string(), i.name.clone().into());
+                    player.insert("id".to_string(), i.id.to_string().into());
+                    player.into()
+                })
+                .collect();
+
+            let mut value = Map::new();
+            value.insert("max".to_string(), players.max.into());
+            value.insert("online".to_string(), players.online.into());
+            if !sample.is_empty() {
+                value.insert("sample".to_string(), sample.into());
+            }
+            map.insert("players".to_string(), value.into());
+        }
+
+        map.insert("description".to_string(), self.description.to_value());
+        if let Some(favicon) = &self.favicon {
+            map.insert("favicon".to_string(), favicon.clone().into());
+        }
+        map.insert("enforcesSecureChat".to_string(), self.enforces_secure_chat.into());
+
+        Value::Object(map)
+    }
+}
+
+fn get_str<'a>(value: &'a Value, field: &'static str) -> Result<&'a str, ProtocolError> {
+    value
+        .get(field)
+        .and_then(Value::as_str)
+        .ok_or(ProtocolError::StatusError(field))
+}
+
+fn get_i32(value: &Value, field: &'static str) -> Result<i32, ProtocolError> {
+    value
+        .get(field)
+        .and_then(Value::as_i64)
+        .and_then(|i| i32::try_from(i).ok())
+        .ok_or(ProtocolError::StatusError(field))
+}
+
+/// Result of [`ping`](ping)
+#[derive(Debug, Clone, PartialEq)]
+pub struct PingResult {
+    pub status: ServerStatus,
+    /// Time between ping request and pong response
+    pub latency: Duration,
+}
+
+/// Get status and latency of server, see [`ping_timeout`](ping_timeout)
+pub fn ping(addr: &str, protocol: i32) -> Result<PingResult, ProtocolError> {
+    ping_timeout(addr, protocol, DEFAULT_TIMEOUT)
+}
+
+/// Get status and latency of server
+///
+/// `addr` is `host` or `host:port`, default port is 25565.
+/// `timeout` is applied to connecting and to every read and write
+pub fn ping_timeout(
+    addr: &str,
+    protocol: i32,
+    timeout: Duration,
+) -> Result<PingResult, ProtocolError> {
+    let (stream, host, port) = connect(addr, timeout)?;
+    let mut conn = MCConnTcp::new(stream);
+
+    conn.send(&Handshake {
+        protocol_version: protocol,
+        server_address: host.to_string(),
+        server_port: port,
+        next_state: NextState::Status,
+    })?;
+    conn.send(&StatusRequest)?;
+    let status = conn.recv::<StatusResponse>()?.status()?;
+
+    let payload = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|i| i.as_millis() as i64)
+        .unwrap_or_default();
+    let start = Instant::now();
+    conn.send(&PingRequest { payload })?;
+    let pong = conn.recv::<PongResponse>()?;
+    let latency = start.elapsed();
+
+    conn.close();
+
+    if pong.payload != payload {
+        return Err(ProtocolError::InvalidValueError {
+            field: "Pong Payload",
+            value: pong.payload,
+        });
+    }
+
+    Ok(PingResult { status, latency })
+}
+
+/// Connect to `addr` with `timeout` applied to connecting and to every read and write,
+/// returns stream with host and port of `addr`
+fn connect(addr: &str, timeout: Duration) -> Result<(TcpStream, &str, u16), ProtocolError> {
+    let (host, port) = split_addr(addr)?;
+    let socket_addr = if host.contains(':') {
+        format!("[{host}]:{port}")
+    } else {
+        format!("{host}:{port}")
+    };
+
+    let stream = TcpStream::connect_timeout(&resolve_addr(&socket_addr)?, timeout)
+        .map_err(ProtocolError::StreamConnectError)?;
+    stream
+        .set_read_timeout(Some(timeout))
+        .and_then(|_| stream.set_write_timeout(Some(timeout)))
+        .map_err(ProtocolError::StreamConnectError)?;
+    Ok((stream, host, port))
+}
+
+/// Split address to host and port, IPv6 host must be in brackets if port is set
+fn split_addr(addr: &str) -> Result<(&str, u16), ProtocolError> {
+    let (host, port) = match addr.rsplit_once(':') {
+        Some((host, port)) if !host.contains(':') || host.ends_with(']') => (
+            host,
+            port.parse()
+                .map_err(|_| ProtocolError::AddressParseError(None))?,
+        ),
+        _ => (addr, DEFAULT_PORT),
+    };
+    Ok((host.trim_start_matches('[').trim_end_matches(']'), port))
+}
@@ -1,6 +1,7 @@
 use uuid::Uuid;
 
 use super::*;
+use crate::data::nbt::Nbt;
 use std::{io::Cursor, net::TcpListener, thread};
 
 #[test]
@@ -8,10 +9,10 @@ fn test_data_transfer() -> Result<(), ProtocolError> {
 
     thread::spawn(move || -> Result<(), ProtocolError> {
         let listener =
-            TcpListener::bind("localhost:44447").or(Err(ProtocolError::StreamConnectError))?;
+            TcpListener::bind("localhost:44447").map_err(ProtocolError::StreamConnectError)?;
 
         for stream in listener.incoming() {
-            let mut stream = MCConnTcp::new(stream.or(Err(ProtocolError::StreamConnectError))?);
+            let mut stream = MCConnTcp::new(stream.map_err(ProtocolError::StreamConnectError)?);
 
             stream.set_compression(Some(5));
 
@@ -23,7 +24,7 @@ fn test_data_transfer() -> Result<(), ProtocolError> {
                 pack.write_bytes(&packet.read_bytes(10)?)?;
                 pack.write_double(packet.read_double()?)?;
                 pack.write_float(packet.read_float()?)?;
-                pack.write_i128_varint(packet.read_i128_varint()?)?;
+                pack.write_i128_zigzag_varint(packet.read_i128_zigzag_varint()?)?;
                 pack.write_u128_varint(packet.read_u128_varint()?)?;
                 pack.write_int(packet.read_int()?)?;
                 pack.write_long(packet.read_long()?)?;
@@ -43,7 +44,7 @@ fn test_data_transfer() -> Result<(), ProtocolError> {
                 pack.write_bytes(&packet.read_bytes(10)?)?;
                 pack.write_double(packet.read_double()?)?;
                 pack.write_float(packet.read_float()?)?;
-                pack.write_i128_varint(packet.read_i128_varint()?)?;
+                pack.write_i128_zigzag_varint(packet.read_i128_zigzag_varint()?)?;
                 pack.write_u128_varint(packet.read_u128_varint()?)?;
                 pack.write_int(packet.read_int()?)?;
                 pack.write_long(packet.read_long()?)?;
@@ -57,9 +58,11 @@ fn test_data_transfer() -> Result<(), ProtocolError> {
         Ok(())
     });
     
-    let conn = MCConnTcp::connect("localhost:44447");
+    let mut conn = MCConnTcp::connect("localhost:44447");
 
-    while let Err(_) = conn {}
+    while conn.is_err() {
+        conn = MCConnTcp::connect("localhost:44447");
+    }
 
     let mut conn = conn?;
 
@@ -71,12 +74,12 @@ fn test_data_transfer() -> Result<(), ProtocolError> {
         pack.write_bytes(&vec![0x01, 0x56, 0x47, 0x48, 0xf5, 0xc2, 0x45, 0x98, 0xde, 0x99])?;
         pack.write_double(123456789.123456789f64)?;
         pack.write_float(789456.44422f32)?;
-        pack.write_i128_varint(468927513325566)?;
+        pack.write_i128_zigzag_varint(468927513325566)?;
         pack.write_u128_varint(99859652365236523)?;
         pack.write_int(77861346i32)?;
         pack.write_long(789465123545678946i64)?;
         pack.write_short(1233i16)?;
-        pack.write_uuid(&Uuid::try_parse("550e8400-e29b-41d4-a716-446655440000").map_err(|_| ProtocolError::CloneError)?)?;
+        pack.write_uuid(&Uuid::try_parse("550e8400-e29b-41d4-a716-446655440000").unwrap())?;
         pack.write_string("&packet.read_string()?")?;
         Ok(())
     })?)?;
@@ -88,12 +91,12 @@ fn test_data_transfer() -> Result<(), ProtocolError> {
     assert_eq!(packet.read_bytes(10)?, vec![0x01, 0x56, 0x47, 0x48, 0xf5, 0xc2, 0x45, 0x98, 0xde, 0x99]);
     assert_eq!(packet.read_double()?, 123456789.123456789f64);
     assert_eq!(packet.read_float()?, 789456.44422f32);
-    assert_eq!(packet.read_i128_varint()?, 468927513325566);
+    assert_eq!(packet.read_i128_zigzag_varint()?, 468927513325566);
     assert_eq!(packet.read_u128_varint()?, 99859652365236523);
     assert_eq!(packet.read_int()?, 77861346i32);
     assert_eq!(packet.read_long()?, 789465123545678946i64);
     assert_eq!(packet.read_short()?, 1233i16);
-    assert_eq!(packet.read_uuid()?, Uuid::try_parse("550e8400-e29b-41d4-a716-446655440000").map_err(|_| ProtocolError::CloneError)?);
+    assert_eq!(packet.read_uuid()?, Uuid::try_parse("550e8400-e29b-41d4-a716-446655440000").unwrap());
     assert_eq!(packet.read_string()?, "&packet.read_string()?");
 
     conn.set_compression(None);
@@ -104,12 +107,12 @@ fn test_data_transfer() -> Result<(), ProtocolError> {
         pack.write_bytes(&vec![0x01, 0x56, 0x47, 0x48, 0xf5, 0xc2, 0x45, 0x98, 0xde, 0x99])?;
         pack.write_double(123456789.123456789f64)?;
         pack.write_float(789456.44422f32)?;
-        pack.write_i128_varint(468927513325566)?;
+        pack.write_i128_zigzag_varint(468927513325566)?;
         pack.write_u128_varint(99859652365236523)?;
         pack.write_int(77861346i32)?;
         pack.write_long(789465123545678946i64)?;
         pack.write_short(1233i16)?;
-        pack.write_uuid(&Uuid::try_parse("550e8400-e29b-41d4-a716-446655440000").map_err(|_| ProtocolError::CloneError)?)?;
+        pack.write_uuid(&Uuid::try_parse("550e8400-e29b-41d4-a716-446655440000").unwrap())?;
         pack.write_string("&packet.read_string()?")?;
         Ok(())
     })?)?;
@@ -121,12 +124,12 @@ fn test_data_transfer() -> Result<(), ProtocolError> {
     assert_eq!(packet.read_bytes(10)?, vec![0x01, 0x56, 0x47, 0x48, 0xf5, 0xc2, 0x45, 0x98, 0xde, 0x99]);
     assert_eq!(packet.read_double()?, 123456789.123456789f64);
     assert_eq!(packet.read_float()?, 789456.44422f32);
-    assert_eq!(packet.read_i128_varint()?, 468927513325566);
+    assert_eq!(packet.read_i128_zigzag_varint()?, 468927513325566);
     assert_eq!(packet.read_u128_varint()?, 99859652365236523);
     assert_eq!(packet.read_int()?, 77861346i32);
     assert_eq!(packet.read_long()?, 789465123545678946i64);
     assert_eq!(packet.read_short()?, 1233i16);
-    assert_eq!(packet.read_uuid()?, Uuid::try_parse("550e8400-e29b-41d4-a716-446655440000").map_err(|_| ProtocolError::CloneError)?);
+    assert_eq!(packet.read_uuid()?, Uuid::try_parse("550e8400-e29b-41d4-a716-446655440000").unwrap());
     assert_eq!(packet.read_string()?, "&packet.read_string()?");
 
     Ok(())
@@ -153,3 +156,1616 @@ fn test_compression() -> Result<(), ProtocolError> {
 
     Ok(())
 }
+
+#[test]
+fn test_varint() -> Result<(), ProtocolError> {
+    let mut buf = Vec::new();
+    buf.write_varint(-1)?;
+    assert_eq!(buf, [0xff, 0xff, 0xff, 0xff, 0x0f]);
+
+    let mut buf = Vec::new();
+    buf.write_varlong(i64::MIN)?;
+    assert_eq!(buf, [0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x01]);
+
+    let mut buf = Vec::new();
+    buf.write_varint(2147483647)?;
+    buf.write_varint(-2147483648)?;
+    buf.write_varlong(-1)?;
+    buf.write_varint(300)?;
+
+    let mut cursor = Cursor::new(buf);
+    assert_eq!(cursor.read_varint()?, 2147483647);
+    assert_eq!(cursor.read_varint_size()?, (-2147483648, 5));
+    assert_eq!(cursor.read_varlong_size()?, (-1, 10));
+    assert_eq!(cursor.read_varint()?, 300);
+
+    let mut cursor = Cursor::new(vec![0xff, 0xff, 0xff, 0xff, 0xff, 0x01]);
+    assert!(matches!(cursor.read_varint(), Err(ProtocolError::VarIntError { .. })));
+
+    let mut cursor = Cursor::new(vec![0x80; 11]);
+    assert!(matches!(cursor.read_varlong(), Err(ProtocolError::VarIntError { .. })));
+
+    let mut buf = Vec::new();
+    buf.write_i32_zigzag_varint(-1)?;
+    assert_eq!(buf, [0x01]);
+    assert_eq!(Cursor::new(buf).read_i32_zigzag_varint()?, -1);
+
+    Ok(())
+}
+
+#[test]
+fn test_packet_id() -> Result<(), ProtocolError> {
+    let mut conn = MCConn::new(Cursor::new(Vec::new()));
+
+    conn.write_packet(&Packet::from_bytes(0x7f, b"small"))?;
+    conn.write_packet(&Packet::from_bytes(0x80, b"two bytes"))?;
+    conn.write_packet(&Packet::from_bytes(0x12345, b"three bytes"))?;
+
+    conn.get_mut().set_position(0);
+
+    let mut packet = conn.read_packet()?;
+    assert_eq!(packet.id(), 0x7f);
+    assert_eq!(packet.read_bytes(5)?, b"small");
+
+    let mut packet = conn.read_packet()?;
+    assert_eq!(packet.id(), 0x80);
+    assert_eq!(packet.read_bytes(9)?, b"two bytes");
+
+    let mut packet = conn.read_packet()?;
+    assert_eq!(packet.id(), 0x12345);
+    assert_eq!(packet.read_bytes(11)?, b"three bytes");
+
+    Ok(())
+}
+
+#[test]
+fn test_encryption() -> Result<(), ProtocolError> {
+    let mut conn = MCConn::new(Cursor::new(Vec::new()));
+    conn.enable_encryption(b"0123456789abcdef")?;
+    conn.set_compression(Some(5));
+
+    assert!(conn.is_encrypted());
+
+    conn.write_packet(&Packet::from_bytes(0x01, b"1234567890qwertyuiopasdfghjklzxcvbnm"))?;
+    conn.write_packet(&Packet::from_bytes(0x02, b"abc"))?;
+
+    assert!(!conn.get_ref().get_ref().windows(3).any(|i| i == b"abc"));
+
+    conn.get_mut().set_position(0);
+
+    let mut packet = conn.read_packet()?;
+    assert_eq!(packet.id(), 0x01);
+    assert_eq!(packet.read_bytes(36)?, b"1234567890qwertyuiopasdfghjklzxcvbnm");
+
+    let mut packet = conn.read_packet()?;
+    assert_eq!(packet.id(), 0x02);
+    assert_eq!(packet.read_bytes(3)?, b"abc");
+
+    let mut conn = MCConn::new(Cursor::new(Vec::new()));
+    assert!(matches!(
+        conn.enable_encryption(b"short"),
+        Err(ProtocolError::EncryptionError { expected: 16, actual: 5 })
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_encryption_clone() -> Result<(), ProtocolError> {
+    let listener = TcpListener::bind("localhost:44448").map_err(ProtocolError::StreamConnectError)?;
+
+    thread::spawn(move || -> Result<(), ProtocolError> {
+        let stream = listener.accept().map_err(ProtocolError::StreamConnectError)?.0;
+        let mut conn = MCConnTcp::new(stream);
+
+        conn.enable_encryption(b"0123456789abcdef")?;
+
+        for _ in 0..3 {
+            let packet = conn.read_packet()?;
+            conn.write_packet(&packet)?;
+        }
+
+        Ok(())
+    });
+
+    let mut conn = MCConnTcp::connect("localhost:44448")?;
+    conn.enable_encryption(b"0123456789abcdef")?;
+
+    let mut writer = conn.try_clone()?;
+
+    for i in 0..3 {
+        writer.write_packet(&Packet::from_bytes(i, b"echo"))?;
+
+        let mut packet = conn.read_packet()?;
+        assert_eq!(packet.id(), i);
+        assert_eq!(packet.read_bytes(4)?, b"echo");
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn test_async_connection() -> Result<(), ProtocolError> {
+    let listener = tokio::net::TcpListener::bind("localhost:44449")
+        .await
+        .map_err(ProtocolError::StreamConnectError)?;
+
+    let client = thread::spawn(move || -> Result<(), ProtocolError> {
+        let mut conn = MCConnTcp::connect("localhost:44449")?;
+        conn.set_compression(Some(5));
+        conn.enable_encryption(b"0123456789abcdef")?;
+
+        conn.write_packet(&Packet::build(0x80, |pack| {
+            pack.write_string("1234567890qwertyuiopasdfghjklzxcvbnm")?;
+            pack.write_varint(-5)
+        })?)?;
+
+        let mut packet = conn.read_packet()?;
+        assert_eq!(packet.id(), 0x81);
+        assert_eq!(packet.read_string()?, "1234567890qwertyuiopasdfghjklzxcvbnm");
+        assert_eq!(packet.read_varint()?, -5);
+
+        // frames of one write are read from buffer
+        for id in 0x82..0x85 {
+            conn.queue_packet(&Packet::build(id, |pack| pack.write_bytes(&[7; 100]))?)?;
+        }
+        conn.flush()?;
+
+        Ok(())
+    });
+
+    let stream = listener.accept().await.map_err(ProtocolError::StreamConnectError)?.0;
+    let mut conn = AsyncMCConn::new(stream);
+    conn.set_compression(Some(5));
+    conn.enable_encryption(b"0123456789abcdef")?;
+
+    let mut packet = conn.read_packet().await?;
+    assert_eq!(packet.id(), 0x80);
+
+    conn.write_packet(&Packet::build(0x81, |pack| {
+        pack.write_string(&packet.read_string()?)?;
+        pack.write_varint(packet.read_varint()?)
+    })?)
+    .await?;
+
+    for id in 0x82..0x85 {
+        let mut packet = conn.read_packet().await?;
+        assert_eq!(packet.id(), id);
+        assert_eq!(packet.read_bytes(packet.len())?, [7; 100]);
+    }
+
+    client.join().unwrap()?;
+
+    assert!(matches!(conn.read_packet().await, Err(ProtocolError::ConnectionClosedError)));
+    assert!(!conn.is_alive());
+
+    Ok(())
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn test_async_read_cancel() -> Result<(), ProtocolError> {
+    use std::time::Duration;
+    use tokio::io::AsyncWriteExt;
+
+    let (mut client, server) = tokio::io::duplex(1024);
+    let mut conn = AsyncMCConn::new(server);
+    let frame = encode_packet(None, 0, &Packet::from_bytes(0x01, &[1, 2, 3, 4]))?;
+
+    // read is dropped while waiting for rest of frame
+    client.write_all(&frame[..3]).await.unwrap();
+    let read = tokio::time::timeout(Duration::from_millis(50), conn.read_packet()).await;
+    assert!(read.is_err());
+
+    client.write_all(&frame[3..]).await.unwrap();
+    let mut packet = conn.read_packet().await?;
+    assert_eq!(packet.id(), 0x01);
+    assert_eq!(packet.read_bytes(packet.len())?, [1, 2, 3, 4]);
+
+    Ok(())
+}
+
+#[test]
+fn test_error_context() -> Result<(), ProtocolError> {
+    let mut packet = Packet::from_bytes(0x2a, &[0x00, 0x01]);
+
+    let error = packet.read_int().unwrap_err();
+    assert!(matches!(
+        error,
+        ProtocolError::DataRanOutError {
+            field: Some("Int"),
+            packet_id: Some(0x2a),
+            expected: 4,
+            actual: 2
+        }
+    ));
+    assert_eq!(
+        error.to_string(),
+        "data ran out while reading Int in packet 0x2A: expected 4 bytes, got 2"
+    );
+
+    let mut packet = Packet::from_bytes(0x00, &[0x02, 0xff, 0xfe]);
+    let error = packet.read_string().unwrap_err();
+    assert!(matches!(error, ProtocolError::StringParseError(_)));
+    assert!(error.source().is_some());
+
+    let mut conn = MCConn::new(Cursor::new(vec![0x00]));
+    assert!(matches!(
+        conn.read_packet(),
+        Err(ProtocolError::DataRanOutError { field: Some("Packet ID"), .. })
+    ));
+
+    let Err(error) = MCConnTcp::connect("localhost:1") else {
+        panic!("connected to closed port");
+    };
+    assert!(matches!(error, ProtocolError::StreamConnectError(_)));
+    assert!(error.source().is_some());
+
+    Ok(())
+}
+
+#[test]
+fn test_nbt() -> Result<(), ProtocolError> {
+    let hello_world = b"\x0a\x00\x0bhello world\x08\x00\x04name\x00\x09Bananrama\x00";
+
+    let (name, nbt) = Cursor::new(hello_world).read_named_nbt()?;
+    assert_eq!(name, "hello world");
+    assert_eq!(nbt.get("name"), Some(&Nbt::String("Bananrama".to_string())));
+
+    let mut buf = Vec::new();
+    buf.write_named_nbt(&name, &nbt)?;
+    assert_eq!(buf, hello_world);
+
+    let mut nbt = Nbt::compound();
+    nbt.insert("byte", 1i8);
+    nbt.insert("long", -5i64);
+    nbt.insert("string", "null \0 and emoji 🦀");
+    nbt.insert("list", vec![Nbt::Int(1), Nbt::Int(2)]);
+    nbt.insert("empty", Vec::<Nbt>::new());
+    nbt.insert("ints", vec![1, 2, 3]);
+    nbt.insert("longs", vec![1i64 << 40]);
+    nbt.insert("bytes", vec![-1i8, 0, 1]);
+    nbt.insert("compound", Nbt::compound());
+
+    let mut buf = Vec::new();
+    buf.write_nbt(&nbt)?;
+    assert!(!buf.contains(&0xf0)); // modified UTF-8 has no 4-byte sequences
+    assert_eq!(Cursor::new(buf).read_nbt()?, nbt);
+
+    let mut gzip = Vec::new();
+    nbt.write_compressed(&mut gzip, "Data")?;
+    assert_eq!(Nbt::read_compressed(gzip.as_slice())?, ("Data".to_string(), nbt.clone()));
+
+    let mut zlib = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+    zlib.write_named_nbt("", &nbt)?;
+    let zlib = zlib.finish().unwrap();
+    assert_eq!(Nbt::read_compressed(zlib.as_slice())?, (String::new(), nbt));
+
+    let mut nested = [0x09, 0x00, 0x00, 0x00, 0x01].repeat(600);
+    nested.insert(0, 0x09);
+    assert!(matches!(Cursor::new(nested).read_nbt(), Err(ProtocolError::NbtDepthError)));
+
+    // declared length of truncated byte array is not allocated before data is read
+    let truncated = [0x07, 0x7f, 0xff, 0xff, 0xff, 0x01, 0x02, 0x03];
+    assert!(matches!(
+        Cursor::new(truncated).read_nbt(),
+        Err(ProtocolError::ConnectionClosedError)
+    ));
+    let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    gzip.write_all(&[&[0x0a, 0x00, 0x00, 0x07, 0x00, 0x00][..], &truncated[1..]].concat())
+        .unwrap();
+    assert!(Nbt::read_compressed(gzip.finish().unwrap().as_slice()).is_err());
+
+    let mixed = Nbt::List(vec![Nbt::Int(1), Nbt::Long(2)]);
+    assert!(matches!(Vec::new().write_nbt(&mixed), Err(ProtocolError::NbtListError)));
+
+    let mut compound = Nbt::compound();
+    compound.insert("end", Nbt::End);
+    assert!(matches!(Vec::new().write_nbt(&compound), Err(ProtocolError::NbtEndError)));
+    let ends = Nbt::List(vec![Nbt::End]);
+    assert!(matches!(Vec::new().write_nbt(&ends), Err(ProtocolError::NbtEndError)));
+    assert!(matches!(Vec::new().write_named_nbt("", &Nbt::End), Err(ProtocolError::NbtEndError)));
+
+    let long = Nbt::String("\0".repeat(40000)); // 80000 bytes in modified UTF-8
+    assert!(matches!(
+        Vec::new().write_nbt(&long),
+        Err(ProtocolError::LengthLimitError { field: "NBT String", length: 80000, max: 65535 })
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_typed_packets() -> Result<(), ProtocolError> {
+    use crate::packets::{handshake::*, login::*, status::*};
+
+    let mut conn = MCConn::new(Cursor::new(Vec::new()));
+
+    let handshake = Handshake {
+        protocol_version: 769,
+        server_address: "localhost".to_string(),
+        server_port: 25565,
+        next_state: NextState::Login,
+    };
+    let login_success = LoginSuccess {
+        uuid: Uuid::from_u128(0x550e8400_e29b_41d4_a716_446655440000),
+        username: "Notch".to_string(),
+        properties: vec![Property {
+            name: "textures".to_string(),
+            value: "e30=".to_string(),
+            signature: Some("c2lnbmF0dXJl".to_string()),
+        }],
+    };
+    let encryption_request = EncryptionRequest {
+        server_id: String::new(),
+        public_key: vec![1, 2, 3],
+        verify_token: vec![4, 5, 6, 7],
+        should_authenticate: true,
+    };
+
+    conn.send(&handshake)?;
+    conn.send(&StatusRequest)?;
+    conn.send(&PingRequest { payload: -42 })?;
+    conn.send(&login_success)?;
+    conn.send(&encryption_request)?;
+    conn.send(&LoginPluginResponse { message_id: 5, data: None })?;
+    conn.send(&SetCompression { threshold: 256 })?;
+
+    conn.get_mut().set_position(0);
+
+    assert_eq!(conn.recv::<Handshake>()?, handshake);
+    assert_eq!(conn.recv::<StatusRequest>()?, StatusRequest);
+    assert_eq!(conn.recv::<PongResponse>()?.payload, -42);
+    assert_eq!(conn.recv::<LoginSuccess>()?, login_success);
+    assert_eq!(conn.recv::<EncryptionRequest>()?, encryption_request);
+    assert_eq!(conn.recv::<LoginPluginResponse>()?.data, None);
+    assert_eq!(conn.recv::<SetCompression>()?.threshold, 256);
+
+    let mut packet = Packet::build(0x00, |packet| {
+        packet.write_varint(769)?;
+        packet.write_string("localhost")?;
+        packet.write_unsigned_short(25565)?;
+        packet.write_varint(7)
+    })?;
+    packet.get_mut().set_position(0);
+    assert!(matches!(
+        Handshake::from_packet(packet),
+        Err(ProtocolError::InvalidValueError { field: "Next State", value: 7 })
+    ));
+
+    assert!(matches!(
+        Handshake::from_packet(Packet::empty(0x01)),
+        Err(ProtocolError::UnexpectedPacketError { expected: 0x00, actual: 0x01 })
+    ));
+    assert!(matches!(
+        PingRequest::from_packet(Packet::empty(0x01)),
+        Err(ProtocolError::DataRanOutError { packet_id: Some(0x01), .. })
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_connection_state() -> Result<(), ProtocolError> {
+    use crate::packets::{
+        handshake::{Handshake, NextState},
+        login::{LoginAcknowledged, LoginStart, SetCompression},
+        AnyPacket,
+    };
+
+    let mut client = MCConn::new(Cursor::new(Vec::new()));
+    assert_eq!(client.state(), ConnectionState::Handshake);
+    assert_eq!(client.side(), None);
+
+    client.send(&Handshake {
+        protocol_version: 769,
+        server_address: "localhost".to_string(),
+        server_port: 25565,
+        next_state: NextState::Login,
+    })?;
+    assert_eq!(client.state(), ConnectionState::Login);
+    assert_eq!(client.side(), Some(Side::Client));
+
+    client.send(&LoginStart { name: "Steve".to_string(), uuid: Uuid::nil() })?;
+    client.send(&LoginAcknowledged)?;
+    assert_eq!(client.state(), ConnectionState::Configuration);
+
+    client.write_packet(&Packet::empty(0x03))?;
+    assert_eq!(client.state(), ConnectionState::Play);
+
+    let mut server = MCConn::new(Cursor::new(client.get_ref().get_ref().clone()));
+
+    assert!(matches!(server.recv_any()?, AnyPacket::Handshake(i) if i.next_state == NextState::Login));
+    assert_eq!(server.side(), Some(Side::Server));
+    assert!(matches!(server.recv_any()?, AnyPacket::LoginStart(i) if i.name == "Steve"));
+    assert!(matches!(server.recv_any()?, AnyPacket::LoginAcknowledged(_)));
+    assert_eq!(server.state(), ConnectionState::Configuration);
+    assert!(matches!(server.recv_any()?, AnyPacket::AcknowledgeFinishConfiguration(_)));
+    assert_eq!(server.state(), ConnectionState::Play);
+
+    // clientbound 0x03 in login is set compression, it doesn't change state
+    let mut server = MCConn::new(Cursor::new(Vec::new()));
+    server.set_state(ConnectionState::Login);
+    server.set_side(Side::Server);
+    server.send(&SetCompression { threshold: 256 })?;
+    assert_eq!(server.state(), ConnectionState::Login);
+
+    Ok(())
+}
+
+#[test]
+fn test_registry() -> Result<(), ProtocolError> {
+    use crate::packets::{
+        handshake::{Handshake, NextState},
+        login::{LoginAcknowledged, LoginSuccess},
+        AnyPacket,
+    };
+    use std::sync::Arc;
+
+    let registry = ProtocolRegistry::vanilla();
+    assert_eq!(registry.versions(), vec![764, 765, 766, 767, 768, 769]);
+    assert_eq!(
+        registry.id(765, ConnectionState::Configuration, Direction::Clientbound, "finish_configuration"),
+        Some(0x02)
+    );
+    assert_eq!(
+        registry.name(769, ConnectionState::Play, Direction::Serverbound, 0x0E),
+        Some("acknowledge_configuration")
+    );
+    assert_eq!(
+        registry.translate(764, 769, ConnectionState::Configuration, Direction::Clientbound, 0x07),
+        Some(0x0C)
+    );
+
+    let registry = ProtocolRegistry::parse(
+        "# custom\n100,102-103 login clientbound login_success 0x10\n",
+    )?;
+    assert_eq!(registry.versions(), vec![100, 102, 103]);
+    assert!(matches!(
+        ProtocolRegistry::parse("769 login clientbound login_success\n"),
+        Err(ProtocolError::RegistryParseError { line: 1 })
+    ));
+    assert!(matches!(
+        registry.to_packet(101, &LoginAcknowledged),
+        Err(ProtocolError::UnsupportedPacketError { protocol: 101, name: "login_acknowledged" })
+    ));
+
+    let success = LoginSuccess { uuid: Uuid::nil(), username: "Steve".to_string(), properties: vec![] };
+    let mut packet = registry.to_packet(102, &success)?;
+    assert_eq!(packet.id(), 0x10);
+    packet.get_mut().set_position(0);
+    assert_eq!(registry.from_packet::<LoginSuccess>(102, packet.clone())?, success);
+    assert!(matches!(
+        registry.decode(102, ConnectionState::Login, Direction::Clientbound, packet)?,
+        AnyPacket::Unknown(i) if i.id() == 0x10
+    ));
+
+    // fields that differ between versions
+    use crate::packets::{configuration::RegistryData, login::EncryptionRequest};
+    let registry = ProtocolRegistry::vanilla();
+    let request = EncryptionRequest {
+        server_id: String::new(),
+        public_key: vec![1, 2],
+        verify_token: vec![3],
+        should_authenticate: false,
+    };
+    let mut packet = registry.to_packet(765, &request)?;
+    assert_eq!(packet.get_bytes(), &[0, 2, 1, 2, 1, 3]);
+    packet.get_mut().set_position(0);
+    let decoded = registry.from_packet::<EncryptionRequest>(765, packet)?;
+    assert!(decoded.should_authenticate);
+    assert_eq!(registry.to_packet(769, &request)?.get_bytes(), &[0, 2, 1, 2, 1, 3, 0]);
+
+    let mut packet = registry.to_packet(766, &success)?;
+    assert_eq!(packet.len(), 0);
+    assert_eq!(packet.get_bytes().last(), Some(&1)); // strict error handling
+    assert_eq!(registry.to_packet(768, &success)?.get_bytes(), &packet.get_bytes()[..23]);
+    packet.get_mut().set_position(0);
+    packet.set_strict(true);
+    assert_eq!(registry.from_packet::<LoginSuccess>(766, packet)?, success);
+
+    let data = RegistryData { registry_id: "minecraft:dimension_type".to_string(), entries: vec![] };
+    assert!(matches!(
+        registry.to_packet(765, &data),
+        Err(ProtocolError::UnsupportedPacketError { protocol: 765, name: "registry_data" })
+    ));
+    let packet = Packet::from_bytes(0x05, &[0x0a, 0x00]);
+    assert!(matches!(
+        registry.decode(765, ConnectionState::Configuration, Direction::Clientbound, packet)?,
+        AnyPacket::Unknown(i) if i.id() == 0x05
+    ));
+
+    // acknowledge configuration is 0x0C in 766 and 0x0E in 769
+    let mut client = MCConn::new(Cursor::new(Vec::new()));
+    client.set_registry(Arc::new(ProtocolRegistry::vanilla()));
+    client.send(&Handshake {
+        protocol_version: 766,
+        server_address: "localhost".to_string(),
+        server_port: 25565,
+        next_state: NextState::Login,
+    })?;
+    assert_eq!(client.protocol_version(), Some(766));
+    client.send_versioned(&LoginAcknowledged)?;
+    client.write_packet(&Packet::empty(0x03))?;
+    assert_eq!(client.state(), ConnectionState::Play);
+    client.write_packet(&Packet::empty(0x0E))?;
+    assert_eq!(client.state(), ConnectionState::Play);
+    client.write_packet(&Packet::empty(0x0C))?;
+    assert_eq!(client.state(), ConnectionState::Configuration);
+
+    Ok(())
+}
+
+#[test]
+fn test_text_component() -> Result<(), ProtocolError> {
+    use crate::text::{ClickEvent, Color, HoverEvent};
+
+    let component = TextComponent::text("Hello ")
+        .color(Color::Gold)
+        .bold(true)
+        .click(ClickEvent::ChangePage(2))
+        .hover(HoverEvent::ShowEntity {
+            entity_type: "minecraft:pig".to_string(),
+            id: Uuid::from_u128(0x0123456789abcdef0123456789abcdef),
+            name: Some(Box::new("Pig".into())),
+        })
+        .extra(TextComponent::translate("chat.type.text", vec!["Steve".into(), "hi".into()]))
+        .extra(TextComponent::text("!").color(Color::Rgb(0x12AB34)).bold(false));
+
+    assert_eq!(component.to_plain(), "Hello chat.type.text!");
+    assert_eq!(TextComponent::from_json(&component.to_json())?, component);
+    assert_eq!(TextComponent::from_nbt(&component.to_nbt())?, component);
+    assert_eq!(TextComponent::text("plain").to_nbt(), Nbt::String("plain".to_string()));
+
+    let mut packet = Packet::empty(0x00);
+    packet.write_text_component(&component)?;
+    packet.write_json_text_component(&component)?;
+    packet.get_mut().set_position(0);
+    assert_eq!(packet.read_text_component()?, component);
+    assert_eq!(packet.read_json_text_component()?, component);
+
+    let parsed = TextComponent::from_json(r##"["a", {"text": "b", "color": "#FF0000"}, "c"]"##)?;
+    assert_eq!(parsed.extra.len(), 2);
+    assert_eq!(parsed.extra[0].style.color, Some(Color::Rgb(0xFF0000)));
+    assert!(matches!(
+        TextComponent::from_json(r#"{"text": "a", "color": "pink"}"#),
+        Err(ProtocolError::TextComponentError("invalid color"))
+    ));
+
+    let legacy = TextComponent::from_legacy("§6§lGold §rplain §x&c");
+    assert_eq!(legacy.extra.len(), 2);
+    assert_eq!(legacy.extra[0].style.color, Some(Color::Gold));
+    assert_eq!(legacy.extra[0].style.bold, Some(true));
+    assert_eq!(legacy.extra[1].style, Default::default());
+    assert_eq!(legacy.to_legacy(), "§6§lGold §rplain §x&c");
+    assert_eq!(TextComponent::from_legacy_with("&cred", '&').to_legacy(), "§cred");
+    assert_eq!(component.to_legacy(), "§6§lHello chat.type.text§2!");
+
+    Ok(())
+}
+
+#[test]
+fn test_server_status() -> Result<(), ProtocolError> {
+    use crate::{packets::status::StatusResponse, status::ServerStatus};
+
+    let json = r#"{
+        "version": {"name": "1.21.4", "protocol": 769},
+        "players": {
+            "max": 20,
+            "online": 1,
+            "sample": [
+                {"name": "Notch", "id": "069a79f4-44e9-4726-a5be-fca90e38aaf5"},
+                {"name": "§eJoin now!", "id": ""},
+                {"id": "069a79f4-44e9-4726-a5be-fca90e38aaf5"}
+            ]
+        },
+        "description": "§aHello",
+        "favicon": "data:image/png;base64,AAAA",
+        "enforcesSecureChat": true,
+        "forgeData": {"fmlNetworkVersion": 3}
+    }"#;
+
+    let response = ServerStatus::parse(json)?;
+    assert_eq!(response.version.protocol, 769);
+    let players = response.players.as_ref().unwrap();
+    assert_eq!((players.online, players.max), (1, 20));
+    assert_eq!(players.sample[0].name, "Notch");
+    assert_eq!((players.sample[1].name.as_str(), players.sample[1].id), ("§eJoin now!", Uuid::nil()));
+    assert_eq!(players.sample.len(), 2);
+    assert_eq!(response.description.to_plain(), "§aHello");
+    assert!(response.enforces_secure_chat);
+    assert!(response.extra.contains_key("forgeData"));
+
+    let packet = StatusResponse::from_status(&response);
+    assert_eq!(packet.status()?, response);
+
+    let minimal = ServerStatus::parse(r#"{"version": {"name": "old", "protocol": 47}}"#)?;
+    assert_eq!(minimal.players, None);
+    assert!(!minimal.enforces_secure_chat);
+    assert!(matches!(
+        ServerStatus::parse(r#"{"version": {"name": "old"}}"#),
+        Err(ProtocolError::StatusError("protocol"))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_status_ping() -> Result<(), ProtocolError> {
+    use crate::{
+        packets::{handshake::Handshake, status::*},
+        status::{self, Version},
+    };
+    use std::time::Duration;
+
+    let listener = TcpListener::bind("127.0.0.1:44450").unwrap();
+
+    thread::spawn(move || -> Result<(), ProtocolError> {
+        let mut conn = MCConn::new(listener.accept().unwrap().0);
+        let handshake = conn.recv::<Handshake>()?;
+        assert_eq!((handshake.server_address.as_str(), handshake.server_port), ("127.0.0.1", 44450));
+        conn.recv::<StatusRequest>()?;
+        let response = status::ServerStatus::new(
+            Version { name: "test".to_string(), protocol: handshake.protocol_version },
+            None,
+            "hello",
+        );
+        conn.send(&StatusResponse::from_status(&response))?;
+        let ping = conn.recv::<PingRequest>()?;
+        thread::sleep(Duration::from_millis(20));
+        conn.send(&PongResponse { payload: ping.payload })
+    });
+
+    let result = status::ping("127.0.0.1:44450", 769)?;
+    assert_eq!(result.status.version.protocol, 769);
+    assert_eq!(result.status.description.to_plain(), "hello");
+    assert!(result.latency >= Duration::from_millis(20));
+
+    // server never answers, so ping fails after timeout
+    let listener = TcpListener::bind("127.0.0.1:44451").unwrap();
+    let result = status::ping_timeout("127.0.0.1:44451", 769, Duration::from_millis(100));
+    assert!(matches!(result, Err(ProtocolError::ReadError { .. })));
+    drop(listener);
+
+    Ok(())
+}
+
+#[test]
+fn test_legacy_ping() -> Result<(), ProtocolError> {
+    use crate::status::{
+        legacy::{self, LegacyPing, LegacyStatus},
+        Players, ServerStatus, Version,
+    };
+    use std::io::{Read, Write};
+
+    let listener = TcpListener::bind("127.0.0.1:44452").unwrap();
+
+    thread::spawn(move || -> Result<(), ProtocolError> {
+        let status = LegacyStatus::from_status(&ServerStatus::new(
+            Version { name: "1.21.4".to_string(), protocol: 769 },
+            Some(Players { max: 20, online: 3, sample: Vec::new() }),
+            TextComponent::text("hello").color(crate::text::Color::Green),
+        ));
+
+        for _ in 0..2 {
+            let mut stream = listener.accept().unwrap().0;
+            assert!(legacy::is_legacy_ping(&stream)?);
+            let ping = legacy::read_legacy_ping(&mut stream)?;
+            if let LegacyPing::V1_6 { host, port, .. } = &ping {
+                assert_eq!((host.as_str(), *port), ("127.0.0.1", 44452));
+            }
+            legacy::write_legacy_status(&mut stream, &ping, &status)?;
+        }
+
+        // modern handshake is not legacy ping
+        let stream = listener.accept().unwrap().0;
+        assert!(!legacy::is_legacy_ping(&stream)?);
+        Ok(())
+    });
+
+    let status = legacy::ping("127.0.0.1:44452")?;
+    assert_eq!(status.protocol, 769);
+    assert_eq!(status.version, "1.21.4");
+    assert_eq!(status.motd, "§ahello");
+    assert_eq!((status.online, status.max), (3, 20));
+
+    // beta client sends only 0xFE
+    let mut stream = std::net::TcpStream::connect("127.0.0.1:44452").unwrap();
+    stream.write_all(&[0xFE]).unwrap();
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response).unwrap();
+    assert_eq!(response[0], legacy::KICK_ID);
+    let text: Vec<u16> = response[3..]
+        .chunks(2)
+        .map(|i| u16::from_be_bytes([i[0], i[1]]))
+        .collect();
+    let status = LegacyStatus::parse(&String::from_utf16_lossy(&text))?;
+    assert_eq!((status.motd.as_str(), status.online, status.max), ("hello", 3, 20));
+
+    let mut conn = MCConnTcp::connect("127.0.0.1:44452")?;
+    conn.send(&crate::packets::status::StatusRequest)?;
+
+    let long = LegacyStatus { motd: "a".repeat(70000), ..status };
+    assert!(matches!(
+        legacy::write_legacy_status(&mut Vec::new(), &LegacyPing::Beta, &long),
+        Err(ProtocolError::LengthLimitError { field: "Legacy String", .. })
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_server() -> Result<(), ProtocolError> {
+    use crate::{
+        packets::{handshake::*, login::LoginDisconnect, status::*},
+        server::{Handler, Server},
+        status::{self, legacy::{self, LegacyPing, LegacyStatus}, ServerStatus, Version},
+    };
+
+    struct TestHandler;
+
+    impl Handler for TestHandler {
+        fn status(&self, _conn: &MCConnTcp, handshake: &Handshake) -> Result<ServerStatus, ProtocolError> {
+            Ok(ServerStatus::new(
+                Version { name: "test".to_string(), protocol: handshake.protocol_version },
+                None,
+                "hello",
+            ))
+        }
+
+        fn legacy_status(&self, _conn: &MCConnTcp, _ping: &LegacyPing) -> Option<LegacyStatus> {
+            Some(LegacyStatus {
+                protocol: 127,
+                version: "test".to_string(),
+                motd: "legacy".to_string(),
+                online: 0,
+                max: 1,
+            })
+        }
+    }
+
+    let server = Server::bind("127.0.0.1:44453", TestHandler)?.max_connections(4);
+    let handle = server.shutdown_handle();
+    let thread = thread::spawn(move || server.run());
+
+    let result = status::ping("127.0.0.1:44453", 769)?;
+    assert_eq!(result.status.version.protocol, 769);
+    assert_eq!(result.status.description.to_plain(), "hello");
+
+    assert_eq!(legacy::ping("127.0.0.1:44453")?.motd, "legacy");
+
+    // second status request closes connection
+    let mut conn = MCConnTcp::connect("127.0.0.1:44453")?;
+    conn.send(&Handshake {
+        protocol_version: 769,
+        server_address: "127.0.0.1".to_string(),
+        server_port: 44453,
+        next_state: NextState::Status,
+    })?;
+    conn.send(&StatusRequest)?;
+    conn.send(&StatusRequest)?;
+    conn.recv::<StatusResponse>()?;
+    assert!(matches!(conn.read_packet(), Err(ProtocolError::ConnectionClosedError)));
+
+    // login is not supported by default handler
+    let mut conn = MCConnTcp::connect("127.0.0.1:44453")?;
+    conn.send(&Handshake {
+        protocol_version: 769,
+        server_address: "127.0.0.1".to_string(),
+        server_port: 44453,
+        next_state: NextState::Login,
+    })?;
+    conn.recv::<LoginDisconnect>()?;
+
+    handle.shutdown();
+    thread.join().unwrap()?;
+    assert!(MCConnTcp::connect("127.0.0.1:44453").is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_server_limits() -> Result<(), ProtocolError> {
+    use crate::{
+        packets::handshake::Handshake,
+        server::{Handler, Server, DEFAULT_TIMEOUT},
+        status::{self, ServerStatus, Version},
+    };
+    use std::{
+        io::Read,
+        net::{SocketAddr, TcpStream},
+        sync::{atomic::{AtomicUsize, Ordering}, Arc},
+        time::Duration,
+    };
+
+    struct TestHandler(AtomicUsize);
+
+    impl Handler for TestHandler {
+        fn status(&self, _conn: &MCConnTcp, handshake: &Handshake) -> Result<ServerStatus, ProtocolError> {
+            Ok(ServerStatus::new(
+                Version { name: "test".to_string(), protocol: handshake.protocol_version },
+                None,
+                "hello",
+            ))
+        }
+
+        fn error(&self, _addr: SocketAddr, _error: ProtocolError) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    assert_eq!(DEFAULT_TIMEOUT, Duration::from_secs(30));
+
+    let server = Server::bind("127.0.0.1:44458", TestHandler(AtomicUsize::new(0)))?
+        .max_connections(1)
+        .timeout(Duration::from_millis(300));
+    let server = Arc::new(server);
+    let thread = thread::spawn({
+        let server = server.clone();
+        move || server.run()
+    });
+
+    // idle client takes the only slot until timeout
+    let idle = TcpStream::connect("127.0.0.1:44458").unwrap();
+    while server.connections() == 0 {
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    // client over limit is closed without answer
+    let mut rejected = TcpStream::connect("127.0.0.1:44458").unwrap();
+    let mut buf = Vec::new();
+    assert_eq!(rejected.read_to_end(&mut buf).unwrap(), 0);
+
+    while server.connections() != 0 {
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(server.handler().0.load(Ordering::SeqCst), 1);
+    drop(idle);
+
+    let result = status::ping("127.0.0.1:44458", 769)?;
+    assert_eq!(result.status.description.to_plain(), "hello");
+
+    server.shutdown_handle().shutdown();
+    thread.join().unwrap()?;
+
+    Ok(())
+}
+
+#[test]
+fn test_offline_login() -> Result<(), ProtocolError> {
+    use crate::{
+        login::{is_valid_name, offline_uuid, GameProfile},
+        packets::{handshake::*, login::*, AnyPacket},
+    };
+
+    assert_eq!(
+        offline_uuid("Notch").to_string(),
+        "b50ad385-829d-3141-a216-7e7d7539ba7f"
+    );
+    assert!(is_valid_name("Notch_2009"));
+    assert!(!is_valid_name(""));
+    assert!(!is_valid_name("bad name"));
+    assert!(!is_valid_name("Мистер"));
+    assert!(!is_valid_name("seventeen_letters"));
+
+    let listener = TcpListener::bind("127.0.0.1:44454").unwrap();
+
+    let server = thread::spawn(move || -> Result<GameProfile, ProtocolError> {
+        let mut conn = MCConn::new(listener.accept().unwrap().0);
+        conn.recv::<Handshake>()?;
+        let profile = conn.accept_offline_login(Some(64))?;
+        assert_eq!(conn.state(), ConnectionState::Configuration);
+        assert_eq!(conn.compression(), Some(64));
+
+        let mut packet = conn.read_packet()?;
+        assert_eq!(packet.read_bytes(packet.len())?, vec![7; 1000]);
+        Ok(profile)
+    });
+
+    let mut conn = MCConnTcp::connect("127.0.0.1:44454")?;
+    conn.send(&Handshake {
+        protocol_version: 769,
+        server_address: "127.0.0.1".to_string(),
+        server_port: 44454,
+        next_state: NextState::Login,
+    })?;
+    let profile = conn.login_offline("Notch")?;
+    assert_eq!(profile, GameProfile::offline("Notch"));
+    assert_eq!(conn.state(), ConnectionState::Configuration);
+    assert_eq!(conn.compression(), Some(64));
+
+    conn.write_packet(&Packet::build(0x00, |i| i.write_bytes(&[7; 1000]))?)?;
+    assert_eq!(server.join().unwrap()?, profile);
+
+    let listener = TcpListener::bind("127.0.0.1:44454").unwrap();
+    let server = thread::spawn(move || -> Result<(), ProtocolError> {
+        let mut conn = MCConn::new(listener.accept().unwrap().0);
+        conn.recv::<Handshake>()?;
+        assert!(matches!(
+            conn.accept_offline_login(None),
+            Err(ProtocolError::LoginError("invalid player name"))
+        ));
+        Ok(())
+    });
+
+    let mut conn = MCConnTcp::connect("127.0.0.1:44454")?;
+    conn.send(&Handshake {
+        protocol_version: 769,
+        server_address: "127.0.0.1".to_string(),
+        server_port: 44454,
+        next_state: NextState::Login,
+    })?;
+    assert!(matches!(
+        conn.login_offline("bad name"),
+        Err(ProtocolError::LoginError("invalid player name"))
+    ));
+    conn.send_versioned(&LoginStart {
+        name: "bad name".to_string(),
+        uuid: offline_uuid("bad name"),
+    })?;
+    assert!(matches!(conn.recv_any()?, AnyPacket::LoginDisconnect(_)));
+    server.join().unwrap()?;
+
+    Ok(())
+}
+
+#[cfg(feature = "rsa")]
+#[test]
+fn test_online_login() -> Result<(), ProtocolError> {
+    use crate::{
+        login::{online::*, GameProfile},
+        packets::{handshake::*, login::*},
+    };
+    use rsa::{pkcs8::DecodePublicKey, rand_core::OsRng, Pkcs1v15Encrypt, RsaPublicKey};
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::{IpAddr, TcpStream},
+        sync::mpsc,
+    };
+
+    assert_eq!(server_hash("Notch", &[], &[]), "4ed1f46bbe04bc756bcb17c0c7ce3e4632f06a48");
+    assert_eq!(server_hash("jeb_", &[], &[]), "-7c9d5b0044c130109a5d7b5fb5c317c02b4e28c1");
+    assert_eq!(server_hash("simon", &[], &[]), "88e16a1019277b15d58faf0541e11910eb756f6");
+
+    // mock of session server, answers one request and sends its line back
+    let (request_sender, request_receiver) = mpsc::channel();
+    let session_listener = TcpListener::bind("127.0.0.1:44456").unwrap();
+    thread::spawn(move || {
+        let mut stream = session_listener.accept().unwrap().0;
+        let mut line = String::new();
+        BufReader::new(&mut stream).read_line(&mut line).unwrap();
+        request_sender.send(line).unwrap();
+
+        let body = r#"{"id":"069a79f444e94726a5befca90e38aaf5","name":"Notch",
+            "properties":[{"name":"textures","value":"e30=","signature":"c2ln"}]}"#;
+        write!(
+            stream,
+            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        )
+        .unwrap();
+    });
+
+    struct MockSessionServer;
+
+    impl SessionServer for MockSessionServer {
+        fn has_joined(
+            &self,
+            username: &str,
+            server_hash: &str,
+            ip: Option<IpAddr>,
+        ) -> Result<Option<GameProfile>, ProtocolError> {
+            let url = has_joined_url("http://127.0.0.1:44456", username, server_hash, ip);
+            let path = url.trim_start_matches("http://127.0.0.1:44456");
+
+            let mut stream = TcpStream::connect("127.0.0.1:44456").unwrap();
+            write!(stream, "GET {path} HTTP/1.1\r\nHost: 127.0.0.1\r\n\r\n").unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+
+            let (head, body) = response.split_once("\r\n\r\n").unwrap();
+            match head.starts_with("HTTP/1.1 200") {
+                true => GameProfile::from_json(body).map(Some),
+                false => Ok(None),
+            }
+        }
+    }
+
+    let listener = TcpListener::bind("127.0.0.1:44455").unwrap();
+
+    let server = thread::spawn(move || -> Result<GameProfile, ProtocolError> {
+        let key = ServerKey::generate()?;
+        let mut conn = MCConn::new(listener.accept().unwrap().0);
+        conn.recv::<Handshake>()?;
+        conn.accept_online_login(&key, &MockSessionServer, "127.0.0.1".parse().ok(), Some(256))
+    });
+
+    let mut conn = MCConnTcp::connect("127.0.0.1:44455")?;
+    conn.send(&Handshake {
+        protocol_version: 769,
+        server_address: "127.0.0.1".to_string(),
+        server_port: 44455,
+        next_state: NextState::Login,
+    })?;
+    conn.send(&LoginStart {
+        name: "Notch".to_string(),
+        uuid: Uuid::nil(),
+    })?;
+
+    let request = conn.recv::<EncryptionRequest>()?;
+    assert!(request.should_authenticate);
+    let public_key = RsaPublicKey::from_public_key_der(&request.public_key).unwrap();
+    let shared_secret = [42; 16];
+    conn.send(&EncryptionResponse {
+        shared_secret: public_key.encrypt(&mut OsRng, Pkcs1v15Encrypt, &shared_secret).unwrap(),
+        verify_token: public_key
+            .encrypt(&mut OsRng, Pkcs1v15Encrypt, &request.verify_token)
+            .unwrap(),
+    })?;
+    conn.enable_encryption(&shared_secret)?;
+
+    let threshold = conn.recv::<SetCompression>()?.threshold;
+    conn.set_compression(Some(threshold as usize));
+    let success = conn.recv::<LoginSuccess>()?;
+    conn.send(&LoginAcknowledged)?;
+
+    let profile = server.join().unwrap()?;
+    assert_eq!(profile.id, success.uuid);
+    assert_eq!(profile.name, "Notch");
+    assert_eq!(success.properties.len(), 1);
+    assert_eq!(success.properties[0].signature.as_deref(), Some("c2ln"));
+
+    let hash = server_hash("", &shared_secret, &request.public_key);
+    let line = request_receiver.recv().unwrap();
+    assert!(line.starts_with(&format!(
+        "GET /session/minecraft/hasJoined?username=Notch&serverId={hash}&ip=127.0.0.1 "
+    )));
+
+    Ok(())
+}
+
+#[test]
+fn test_configuration() -> Result<(), ProtocolError> {
+    use crate::{
+        configuration::RegistrySet,
+        packets::{configuration::*, handshake::*, AnyPacket},
+    };
+
+    let registry = ProtocolRegistry::vanilla();
+    assert_eq!(
+        registry.id(764, ConnectionState::Configuration, Direction::Clientbound, "registry_data"),
+        Some(0x05)
+    );
+    assert!(registry.packet_id::<RegistryData>(764).is_err()); // fields of 1.20.2 differ
+    assert_eq!(registry.packet_id::<ServerboundKnownPacks>(769)?, 0x07);
+    assert!(registry.packet_id::<ClientboundKnownPacks>(765).is_err());
+
+    let core = KnownPack {
+        namespace: "minecraft".to_string(),
+        id: "core".to_string(),
+        version: "1.21.4".to_string(),
+    };
+
+    let mut data = Nbt::compound();
+    data.insert("height", Nbt::Int(384));
+
+    let extra = KnownPack {
+        namespace: "test".to_string(),
+        id: "extra".to_string(),
+        version: "1".to_string(),
+    };
+
+    let mut set = RegistrySet::new();
+    set.feature_flags.push("minecraft:vanilla".to_string());
+    set.known_packs.push(core.clone());
+    set.known_packs.push(extra.clone());
+    set.registries.push(RegistryData {
+        registry_id: "minecraft:dimension_type".to_string(),
+        entries: vec![
+            RegistryEntry { id: "minecraft:overworld".to_string(), data: None },
+            RegistryEntry { id: "test:custom".to_string(), data: Some(data) },
+        ],
+    });
+    set.tags.push(RegistryTags {
+        registry: "minecraft:block".to_string(),
+        tags: vec![Tag { name: "minecraft:logs".to_string(), entries: vec![1, 2, 300] }],
+    });
+    assert_eq!(set.required_packs(), [&core]); // entry of test namespace has data
+
+    let mut old_conn = MCConn::new(Cursor::new(Vec::new()));
+    old_conn.set_protocol_version(765);
+    assert!(matches!(
+        old_conn.configure_client(&set),
+        Err(ProtocolError::ConfigurationError(_))
+    ));
+
+    let listener = TcpListener::bind("127.0.0.1:44457").unwrap();
+
+    let server = thread::spawn(move || -> Result<Vec<KnownPack>, ProtocolError> {
+        let mut conn = MCConn::new(listener.accept().unwrap().0);
+        conn.recv::<Handshake>()?;
+        conn.accept_offline_login(None)?;
+        let packs = conn.configure_client(&set)?;
+        assert_eq!(conn.state(), ConnectionState::Play);
+        Ok(packs)
+    });
+
+    let mut conn = MCConnTcp::connect("127.0.0.1:44457")?;
+    conn.send(&Handshake {
+        protocol_version: 769,
+        server_address: "127.0.0.1".to_string(),
+        server_port: 44457,
+        next_state: NextState::Login,
+    })?;
+    conn.login_offline("Steve")?;
+
+    let mut registries = Vec::new();
+    loop {
+        match conn.recv_any()? {
+            AnyPacket::FeatureFlags(packet) => assert_eq!(packet.features, ["minecraft:vanilla"]),
+            AnyPacket::ClientboundKnownPacks(packet) => {
+                assert_eq!(packet.packs, [core.clone(), extra.clone()]);
+                conn.send(&ServerboundKnownPacks { packs: vec![core.clone()] })?;
+            }
+            AnyPacket::RegistryData(packet) => registries.push(packet),
+            AnyPacket::UpdateTags(packet) => {
+                assert_eq!(packet.registries[0].tags[0].entries, [1, 2, 300]);
+            }
+            AnyPacket::FinishConfiguration(_) => {
+                conn.send(&AcknowledgeFinishConfiguration)?;
+                break;
+            }
+            packet => panic!("unexpected packet {packet:?}"),
+        }
+    }
+
+    assert_eq!(conn.state(), ConnectionState::Play);
+    assert_eq!(registries.len(), 1);
+    assert_eq!(registries[0].entries[0].data, None);
+    assert_eq!(
+        registries[0].entries[1].data.as_ref().and_then(|i| i.get("height")),
+        Some(&Nbt::Int(384))
+    );
+    assert_eq!(server.join().unwrap()?, [core]);
+
+    Ok(())
+}
+
+#[test]
+fn test_data_types() -> Result<(), ProtocolError> {
+    use crate::data::{Angle, BitSet, Identifier, Position};
+
+    // example from protocol docs
+    let position = Position::new(18357644, 831, -20882616);
+    assert_eq!(position.to_packed(), 0x4607632C15B4833F);
+    assert_eq!(Position::from_packed(position.to_packed()), position);
+    let position_min = Position::new(-1, -2048, -33554432);
+    assert_eq!(Position::from_packed(position_min.to_packed()), position_min);
+
+    assert_eq!(Angle::from_degrees(90.0), Angle(64));
+    assert_eq!(Angle::from_degrees(-90.0), Angle(192));
+    assert_eq!(Angle(128).degrees(), 180.0);
+
+    let mut bitset = BitSet::new();
+    bitset.set(0, true);
+    bitset.set(70, true);
+    bitset.set(200, false);
+    assert!(bitset.get(70) && !bitset.get(69) && !bitset.get(1000));
+    assert_eq!(bitset.len(), 71);
+    assert_eq!(bitset.to_longs(), [1, 1 << 6]);
+
+    let mut cleared = bitset.clone();
+    cleared.set(130, true);
+    cleared.set(130, false);
+    assert_eq!(cleared, bitset);
+    assert_eq!(BitSet::from_longs(vec![1, 1 << 6, 0, 0]), bitset);
+    assert!(std::collections::HashSet::from([cleared]).contains(&bitset));
+
+    let mut fixed = BitSet::new();
+    fixed.set(3, true);
+    fixed.set(9, true);
+
+    let mut buf = Vec::new();
+    buf.write_position(&position)?;
+    buf.write_angle(Angle(200))?;
+    buf.write_bitset(&bitset)?;
+    buf.write_fixed_bitset(&fixed, 12)?;
+    buf.write_identifier(&Identifier::parse("stone")?)?;
+    assert!(matches!(
+        buf.write_fixed_bitset(&bitset, 20),
+        Err(ProtocolError::InvalidValueError { field: "Fixed BitSet", value: 70 })
+    ));
+
+    let mut cursor = Cursor::new(buf);
+    assert_eq!(cursor.read_position()?, position);
+    assert_eq!(cursor.read_angle()?, Angle(200));
+    assert_eq!(cursor.read_bitset()?, bitset);
+    let read = cursor.read_fixed_bitset(12)?;
+    assert_eq!(read, fixed);
+    assert!(read.get(3) && read.get(9) && !read.get(8));
+    let identifier = cursor.read_identifier()?;
+    assert_eq!((identifier.namespace(), identifier.path()), ("minecraft", "stone"));
+    assert_eq!(identifier.to_string(), "minecraft:stone");
+
+    assert_eq!(Identifier::parse("my_mod:textures/block.png")?.path(), "textures/block.png");
+    assert!(matches!(Identifier::parse("Minecraft:stone"), Err(ProtocolError::IdentifierError(_))));
+    assert!(Identifier::parse("minecraft/a:stone").is_err());
+    assert!(Identifier::parse(":stone").is_err());
+    assert!(Identifier::parse("minecraft:stone block").is_err());
+
+    let mut cursor = Cursor::new(Vec::new());
+    cursor.write_string("bad:Name")?;
+    cursor.set_position(0);
+    assert!(cursor.read_identifier().is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_prefixed_helpers() -> Result<(), ProtocolError> {
+    use crate::packets::handshake::NextState;
+
+    let list = vec!["a".to_string(), "bc".to_string()];
+
+    let mut buf = Vec::new();
+    buf.write_prefixed_array(&list, |w, i| w.write_string(i))?;
+    buf.write_optional(Some(&5), |w, i| w.write_varint(*i))?;
+    buf.write_optional(None::<&i32>, |w, i| w.write_varint(*i))?;
+    buf.write_varint_enum(NextState::Login)?;
+    buf.write_varint(7)?;
+    buf.write_usize_varint(3)?;
+
+    let mut cursor = Cursor::new(buf);
+    assert_eq!(cursor.read_prefixed_array(|r| r.read_string())?, list);
+    assert_eq!(cursor.read_optional(|r| r.read_varint())?, Some(5));
+    assert_eq!(cursor.read_optional(|r| r.read_varint())?, None);
+    assert_eq!(cursor.read_varint_enum::<NextState>()?, NextState::Login);
+    assert!(matches!(
+        cursor.read_varint_enum::<NextState>(),
+        Err(ProtocolError::InvalidValueError { field: "Next State", value: 7 })
+    ));
+    assert!(matches!(
+        cursor.read_prefixed_array_max(2, |r| r.read_byte()),
+        Err(ProtocolError::LengthLimitError { field: "Prefixed Array", length: 3, max: 2 })
+    ));
+
+    let mut cursor = Cursor::new(Vec::new());
+    cursor.write_usize_varint(usize::MAX >> 33)?;
+    cursor.set_position(0);
+    assert!(matches!(
+        cursor.read_prefixed_array(|r| r.read_byte()),
+        Err(ProtocolError::LengthLimitError { .. })
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_limits() -> Result<(), ProtocolError> {
+    let limits = Limits {
+        max_frame_length: 64,
+        max_decompressed_length: 100,
+        max_string_length: 4,
+        strict: false,
+    };
+
+    let mut frame = Vec::new();
+    frame.write_usize_varint(65)?;
+    assert!(matches!(
+        read_packet_with_limits(&mut Cursor::new(frame), None, &limits),
+        Err(ProtocolError::LengthLimitError { field: "Frame", length: 65, max: 64 })
+    ));
+
+    // hostile length is rejected before data is read
+    let mut conn = MinecraftConnection::new(Cursor::new(Vec::new()));
+    conn.write_usize_varint(usize::MAX >> 1)?;
+    conn.get_mut().set_position(0);
+    assert!(matches!(conn.read_packet(), Err(ProtocolError::LengthLimitError { .. })));
+
+    let packet = Packet::build(0x00, |p| p.write_bytes(&[0; 150]))?;
+    let mut cursor = Cursor::new(encode_packet(Some(0), 1, &packet)?);
+    let length = cursor.read_usize_varint()?;
+    let frame = cursor.read_bytes(length)?;
+    assert!(matches!(
+        decode_packet_with_limits(&frame, Some(0), &limits),
+        Err(ProtocolError::LengthLimitError { field: "Decompressed Packet", length: 151, .. })
+    ));
+
+    // declared length is less than actual length
+    let mut frame = Vec::new();
+    frame.write_usize_varint(10)?;
+    let mut encoder = flate2::write::ZlibEncoder::new(&mut frame, flate2::Compression::best());
+    std::io::Write::write_all(&mut encoder, &[0; 10000]).map_err(ProtocolError::ZlibError)?;
+    encoder.finish().map_err(ProtocolError::ZlibError)?;
+    assert!(matches!(
+        decode_packet_with_limits(&frame, Some(0), &limits),
+        Err(ProtocolError::LengthLimitError { field: "Decompressed Packet", length: 101, .. })
+    ));
+
+    let packet = Packet::build(0x00, |p| {
+        p.write_string("abcd")?;
+        p.write_string("abcde")
+    })?;
+    let frame = encode_packet(None, 1, &packet)?;
+    let mut packet = read_packet_with_limits(&mut Cursor::new(frame), None, &limits)?;
+    assert_eq!(packet.read_string()?, "abcd");
+    assert!(matches!(
+        packet.read_string(),
+        Err(ProtocolError::LengthLimitError { field: "String", length: 5, max: 4 })
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_strict() -> Result<(), ProtocolError> {
+    use crate::packets::status::PingRequest;
+
+    let strict = Limits {
+        strict: true,
+        ..Limits::default()
+    };
+    let compressed = |declared: usize, data: &[u8]| -> Result<Vec<u8>, ProtocolError> {
+        let mut frame = Vec::new();
+        frame.write_usize_varint(declared)?;
+        let mut encoder = flate2::write::ZlibEncoder::new(&mut frame, flate2::Compression::fast());
+        std::io::Write::write_all(&mut encoder, data).map_err(ProtocolError::ZlibError)?;
+        encoder.finish().map_err(ProtocolError::ZlibError)?;
+        Ok(frame)
+    };
+
+    let data = [0x01, 1, 2, 3, 4, 5, 6, 7, 8];
+    let frame = compressed(9, &data)?;
+    assert_eq!(decode_packet_with_limits(&frame, Some(4), &strict)?.get_bytes(), &data[1..]);
+
+    // lenient mode accepts all of these
+    let frame = compressed(5, &data)?;
+    decode_packet(&frame, Some(4))?;
+    assert!(matches!(
+        decode_packet_with_limits(&frame, Some(4), &strict),
+        Err(ProtocolError::DataLengthError { expected: 5, actual: 9 })
+    ));
+
+    let frame = compressed(9, &data)?;
+    decode_packet(&frame, Some(16))?;
+    assert!(matches!(
+        decode_packet_with_limits(&frame, Some(16), &strict),
+        Err(ProtocolError::CompressionThresholdError {
+            length: 9,
+            threshold: 16,
+            compressed: true
+        })
+    ));
+
+    let mut frame = vec![0];
+    frame.extend_from_slice(&data);
+    decode_packet(&frame, Some(4))?;
+    assert!(matches!(
+        decode_packet_with_limits(&frame, Some(4), &strict),
+        Err(ProtocolError::CompressionThresholdError {
+            length: 9,
+            threshold: 4,
+            compressed: false
+        })
+    ));
+
+    let packet = Packet::from_bytes(PingRequest::ID, &[0, 0, 0, 0, 0, 0, 0, 1, 2]);
+    assert_eq!(PingRequest::from_packet(packet.clone())?.payload, 1);
+
+    let mut conn = MinecraftConnection::new(Cursor::new(Vec::new()));
+    conn.write_packet(&packet)?;
+    conn.get_mut().set_position(0);
+    conn.set_limits(strict);
+    assert!(matches!(
+        conn.recv::<PingRequest>(),
+        Err(ProtocolError::TrailingBytesError { packet_id: 0x01, count: 1 })
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_buffered() -> Result<(), ProtocolError> {
+    /// Stream that reads at most `max_read` bytes at once and counts reads
+    struct CountingStream {
+        inner: Cursor<Vec<u8>>,
+        max_read: usize,
+        reads: usize,
+    }
+
+    impl std::io::Read for CountingStream {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.reads += 1;
+            let size = buf.len().min(self.max_read);
+            self.inner.read(&mut buf[..size])
+        }
+    }
+
+    impl std::io::Write for CountingStream {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.inner.write(buf)
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let secret = [7; 16];
+    let mut conn = MinecraftConnection::new(Cursor::new(Vec::new()));
+    conn.set_compression(Some(64));
+    for i in 0..100 {
+        conn.queue_packet(&Packet::build(0x10, |p| p.write_bytes(&vec![i; i as usize]))?)?;
+    }
+    assert!(conn.get_ref().get_ref().is_empty());
+    conn.flush()?;
+    conn.write_packet(&Packet::build(0x11, |p| p.write_string("before encryption"))?)?;
+    conn.enable_encryption(&secret)?;
+    conn.write_packet(&Packet::build(0x12, |p| p.write_string("after encryption"))?)?;
+    let bytes = conn.get_ref().get_ref().clone();
+
+    for max_read in [1, usize::MAX] {
+        let mut conn = MinecraftConnection::new(CountingStream {
+            inner: Cursor::new(bytes.clone()),
+            max_read,
+            reads: 0,
+        });
+        conn.set_compression(Some(64));
+        for i in 0..100 {
+            let packet = conn.read_packet()?;
+            assert_eq!((packet.id(), packet.get_bytes()), (0x10, &vec![i; i as usize][..]));
+        }
+        assert_eq!(conn.read_packet()?.read_string()?, "before encryption");
+        // rest of data is already in buffer and must be decrypted there
+        conn.enable_encryption(&secret)?;
+        assert_eq!(conn.read_packet()?.read_string()?, "after encryption");
+        assert!(matches!(conn.read_packet(), Err(ProtocolError::ConnectionClosedError)));
+
+        if max_read == usize::MAX {
+            assert!(conn.get_ref().reads < 5);
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_packet_buffer() -> Result<(), ProtocolError> {
+    let mut packet = Packet::build(0x05, |p| p.write_bytes(&[1, 2, 3, 4]))?;
+    packet.get_mut().set_position(1);
+    packet.write_bytes(&[5, 6, 7, 8])?;
+    assert_eq!(packet.get_bytes(), &[1, 5, 6, 7, 8]);
+
+    packet.get_mut().set_position(0);
+    assert_eq!(packet.read_slice(2)?, &[1, 5]);
+    assert_eq!(packet.read_bytes(3)?, vec![6, 7, 8]);
+    assert!(matches!(packet.read_slice(1), Err(ProtocolError::DataRanOutError { .. })));
+
+    let mut conn = MinecraftConnection::new(Cursor::new(Vec::new()));
+    conn.queue_packet(&Packet::from_bytes(0x01, &[10; 20]))?;
+    conn.queue_packet(&Packet::from_bytes(0x02, &[20; 20]))?;
+    conn.flush()?;
+    conn.get_mut().set_position(0);
+
+    let first = conn.read_packet()?;
+    let second = conn.read_packet()?;
+    assert_eq!((first.id(), first.get_bytes()), (0x01, &[10; 20][..]));
+    assert_eq!((second.id(), second.get_bytes()), (0x02, &[20; 20][..]));
+    let data: Vec<u8> = second.into_inner().into_inner();
+    assert_eq!(data, [20; 20]);
+
+    // bodies are slices of read buffer, second frame starts after 2 bytes of length and id
+    #[cfg(feature = "bytes")]
+    {
+        conn.queue_packet(&first)?;
+        conn.queue_packet(&Packet::from_bytes(0x02, &[20; 20]))?;
+        conn.flush()?;
+        conn.get_mut().set_position(44);
+
+        let first = conn.read_packet_bytes()?;
+        let second = conn.read_packet_bytes()?;
+        let copied: Packet = second.clone().into();
+        assert_eq!(copied.get_bytes(), &[20; 20]);
+
+        let first = first.into_bytes();
+        let second = second.into_bytes();
+        assert_eq!(second.as_ptr() as usize, first.as_ptr() as usize + first.len() + 2);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_compressor() -> Result<(), ProtocolError> {
+    use crate::compression::{Compressor, Decompressor};
+
+    let mut compressor = Compressor::new(6);
+    let mut decompressor = Decompressor::new();
+
+    // state is reused, every packet must still be decoded by fresh decoder
+    for i in 0..3u8 {
+        let packet = Packet::from_bytes(0x01, &vec![i; 1000 + i as usize]);
+        let mut buf = Vec::new();
+        encode_packet_with(&mut compressor, &mut buf, Some(256), &packet)?;
+        let mut cursor = Cursor::new(buf);
+        let length = cursor.read_usize_varint()?;
+        let frame = cursor.read_bytes(length)?;
+
+        let limits = Limits::new();
+        let decoded = decode_packet_with(&mut decompressor, frame.clone(), Some(256), &limits)?;
+        assert_eq!((decoded.id(), decoded.get_bytes()), (0x01, packet.get_bytes()));
+        let fresh = decode_packet_buffer(frame, Some(256), &limits)?;
+        assert_eq!(fresh.get_bytes(), packet.get_bytes());
+    }
+
+    compressor.set_level(1);
+    assert_eq!(compressor.level(), 1);
+    let data = compressor.compress(&[&[1, 2], &[3; 100]])?.to_vec();
+    let decompressed = decompressor.decompress(&data, 102, 102)?;
+    assert_eq!(&decompressed[..2], &[1, 2]);
+    assert_eq!(&decompressed[2..], &[3; 100]);
+
+    // declared length is wrong, actual length is returned
+    assert_eq!(decompressor.decompress(&data, 10, 200)?.len(), 102);
+    assert_eq!(decompressor.decompress(&data, 150, 200)?.len(), 102);
+    assert!(matches!(
+        decompressor.decompress(&data, 10, 50),
+        Err(ProtocolError::LengthLimitError { field: "Decompressed Packet", length: 51, max: 50 })
+    ));
+    assert!(matches!(
+        decompressor.decompress(&data, 60, 50),
+        Err(ProtocolError::LengthLimitError { length: 60, .. })
+    ));
+    assert!(matches!(
+        decompressor.decompress(&data[..data.len() / 2], 102, 200),
+        Err(ProtocolError::ZlibError(_))
+    ));
+
+    Ok(())
+}
+
+#[cfg(feature = "derive")]
+#[test]
+fn test_derive() -> Result<(), ProtocolError> {
+    #[derive(McRead, McWrite, Debug, PartialEq)]
+    struct Slot(#[mc(varint)] i32, i8);
+
+    #[derive(McRead, McWrite, McPacket, Debug, PartialEq)]
+    #[mc(id = 0x2A)]
+    struct TestPacket {
+        #[mc(varint)]
+        entity_id: i32,
+        fixed: i32,
+        #[mc(varlong)]
+        time: i64,
+        name: String,
+        #[mc(prefixed)]
+        slots: Vec<Slot>,
+        #[mc(prefixed, varint)]
+        ids: Vec<i32>,
+        #[mc(optional)]
+        uuid: Option<Uuid>,
+        #[mc(optional, prefixed)]
+        tags: Option<Vec<String>>,
+    }
+
+    let value = TestPacket {
+        entity_id: 300,
+        fixed: 300,
+        time: -1,
+        name: "Steve".to_string(),
+        slots: vec![Slot(1, -2), Slot(70000, 3)],
+        ids: vec![-5, 128],
+        uuid: None,
+        tags: Some(vec!["a".to_string(), "b".to_string()]),
+    };
+
+    let mut packet = value.to_packet()?;
+    assert_eq!(packet.id(), 0x2A);
+    assert_eq!(&packet.get_bytes()[..6], &[0xac, 0x02, 0x00, 0x00, 0x01, 0x2c]);
+
+    packet.get_mut().set_position(0);
+    assert_eq!(TestPacket::from_packet(packet)?, value);
+
+    Ok(())
+}
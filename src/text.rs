@@ -0,0 +1,840 @@
+//! Text components, used in chat, disconnect reasons, titles and status description
+//!
+//! Component can be written as JSON string or as NBT, that is used in packets since 1.20.3
+
+use crate::{data::nbt::Nbt, ProtocolError};
+use serde_json::{Map, Number, Value};
+use std::{collections::BTreeMap, fmt};
+use uuid::Uuid;
+
+/// Named colors with legacy codes and RGB values
+const COLORS: [(Color, &str, char, u32); 16] = [
+    (Color::Black, "black", '0', 0x000000),
+    (Color::DarkBlue, "dark_blue", '1', 0x0000AA),
+    (Color::DarkGreen, "dark_green", '2', 0x00AA00),
+    (Color::DarkAqua, "dark_aqua", '3', 0x00AAAA),
+    (Color::DarkRed, "dark_red", '4', 0xAA0000),
+    (Color::DarkPurple, "dark_purple", '5', 0xAA00AA),
+    (Color::Gold, "gold", '6', 0xFFAA00),
+    (Color::Gray, "gray", '7', 0xAAAAAA),
+    (Color::DarkGray, "dark_gray", '8', 0x555555),
+    (Color::Blue, "blue", '9', 0x5555FF),
+    (Color::Green, "green", 'a', 0x55FF55),
+    (Color::Aqua, "aqua", 'b', 0x55FFFF),
+    (Color::Red, "red", 'c', 0xFF5555),
+    (Color::LightPurple, "light_purple", 'd', 0xFF55FF),
+    (Color::Yellow, "yellow", 'e', 0xFFFF55),
+    (Color::White, "white", 'f', 0xFFFFFF),
+];
+
+/// Text color, one of 16 named colors or RGB color
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    Black,
+    DarkBlue,
+    DarkGreen,
+    DarkAqua,
+    DarkRed,
+    DarkPurple,
+    Gold,
+    Gray,
+    DarkGray,
+    Blue,
+    Green,
+    Aqua,
+    Red,
+    LightPurple,
+    Yellow,
+    White,
+    /// RGB color like `0xFF8800`
+    Rgb(u32),
+}
+
+impl Color {
+    /// Parse color name like `red` or RGB color like `#FF8800`
+    pub fn parse(name: &str) -> Option<Color> {
+        match name.strip_prefix('#') {
+            Some(hex) if hex.len() == 6 => u32::from_str_radix(hex, 16).ok().map(Color::Rgb),
+            Some(_) => None,
+            None => COLORS.iter().find(|i| i.1 == name).map(|i| i.0),
+        }
+    }
+
+    /// Get color name like `red` or `#FF8800`
+    pub fn name(&self) -> String {
+        match self {
+            Color::Rgb(rgb) => format!("#{rgb:06X}"),
+            color => COLORS.iter().find(|i| i.0 == *color).unwrap().1.to_string(),
+        }
+    }
+
+    /// Get RGB value of color
+    pub fn rgb(&self) -> u32 {
+        match self {
+            Color::Rgb(rgb) => *rgb,
+            color => COLORS.iter().find(|i| i.0 == *color).unwrap().3,
+        }
+    }
+
+    /// Get color by legacy code like `c`
+    pub fn from_legacy_code(code: char) -> Option<Color> {
+        let code = code.to_ascii_lowercase();
+        COLORS.iter().find(|i| i.2 == code).map(|i| i.0)
+    }
+
+    /// Get legacy code of color, RGB color gets code of nearest named color
+    pub fn legacy_code(&self) -> char {
+        let rgb = self.rgb();
+        let distance = |other: u32| {
+            (0..3)
+                .map(|i| {
+                    let a = ((rgb >> (i * 8)) & 0xFF) as i32;
+                    let b = ((other >> (i * 8)) & 0xFF) as i32;
+                    (a - b) * (a - b)
+                })
+                .sum::<i32>()
+        };
+        COLORS.iter().min_by_key(|i| distance(i.3)).unwrap().2
+    }
+}
+
+/// Action on click
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClickEvent {
+    OpenUrl(String),
+    OpenFile(String),
+    RunCommand(String),
+    SuggestCommand(String),
+    ChangePage(i32),
+    CopyToClipboard(String),
+}
+
+/// Tooltip on hover
+#[derive(Debug, Clone, PartialEq)]
+pub enum HoverEvent {
+    ShowText(Box<TextComponent>),
+    ShowItem {
+        /// Item id like `minecraft:stone`
+        id: String,
+        count: i32,
+    },
+    ShowEntity {
+        /// Entity type like `minecraft:pig`
+        entity_type: String,
+        id: Uuid,
+        name: Option<Box<TextComponent>>,
+    },
+}
+
+/// Style of component, `None` values are inherited from parent
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Style {
+    pub color: Option<Color>,
+    pub bold: Option<bool>,
+    pub italic: Option<bool>,
+    pub underlined: Option<bool>,
+    pub strikethrough: Option<bool>,
+    pub obfuscated: Option<bool>,
+    pub font: Option<String>,
+    /// Text inserted into chat on shift click
+    pub insertion: Option<String>,
+    pub click_event: Option<ClickEvent>,
+    pub hover_event: Option<HoverEvent>,
+}
+
+impl Style {
+    /// Fill `None` values from parent style
+    pub fn inherit(&self, parent: &Style) -> Style {
+        Style {
+            color: self.color.or(parent.color),
+            bold: self.bold.or(parent.bold),
+            italic: self.italic.or(parent.italic),
+            underlined: self.underlined.or(parent.underlined),
+            strikethrough: self.strikethrough.or(parent.strikethrough),
+            obfuscated: self.obfuscated.or(parent.obfuscated),
+            font: self.font.clone().or_else(|| parent.font.clone()),
+            insertion: self.insertion.clone().or_else(|| parent.insertion.clone()),
+            click_event: self.click_event.clone().or_else(|| parent.click_event.clone()),
+            hover_event: self.hover_event.clone().or_else(|| parent.hover_event.clone()),
+        }
+    }
+
+    /// Legacy formatting codes of enabled decorations
+    fn legacy_decorations(&self) -> String {
+        [
+            (self.obfuscated, 'k'),
+            (self.bold, 'l'),
+            (self.strikethrough, 'm'),
+            (self.underlined, 'n'),
+            (self.italic, 'o'),
+        ]
+        .iter()
+        .filter(|i| i.0 == Some(true))
+        .map(|i| i.1)
+        .collect()
+    }
+}
+
+/// Content of component
+#[derive(Debug, Clone, PartialEq)]
+pub enum Content {
+    Text(String),
+    Translate {
+        key: String,
+        /// Text shown if client doesn't know key
+        fallback: Option<String>,
+        with: Vec<TextComponent>,
+    },
+    /// Key bound to action, like `key.jump`
+    Keybind(String),
+    Score {
+        name: String,
+        objective: String,
+    },
+    Selector {
+        selector: String,
+        separator: Option<Box<TextComponent>>,
+    },
+}
+
+/// Text component with content, style and children
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextComponent {
+    pub content: Content,
+    pub style: Style,
+    /// Children, inherit style of this component
+    pub extra: Vec<TextComponent>,
+}
+
+impl TextComponent {
+    /// Create component from content
+    pub fn new(content: Content) -> TextComponent {
+        TextComponent {
+            content,
+            style: Style::default(),
+            extra: Vec::new(),
+        }
+    }
+
+    /// Create plain text component
+    pub fn text(text: impl Into<String>) -> TextComponent {
+        TextComponent::new(Content::Text(text.into()))
+    }
+
+    /// Create translated component
+    pub fn translate(key: impl Into<String>, with: Vec<TextComponent>) -> TextComponent {
+        TextComponent::new(Content::Translate {
+            key: key.into(),
+            fallback: None,
+            with,
+        })
+    }
+
+    /// Create keybind component
+    pub fn keybind(key: impl Into<String>) -> TextComponent {
+        TextComponent::new(Content::Keybind(key.into()))
+    }
+
+    /// Set color
+    pub fn color(mut self, color: Color) -> TextComponent {
+        self.style.color = Some(color);
+        self
+    }
+
+    /// Set bold
+    pub fn bold(mut self, bold: bool) -> TextComponent {
+        self.style.bold = Some(bold);
+        self
+    }
+
+    /// Set italic
+    pub fn italic(mut self, italic: bool) -> TextComponent {
+        self.style.italic = Some(italic);
+        self
+    }
+
+    /// Set underlined
+    pub fn underlined(mut self, underlined: bool) -> TextComponent {
+        self.style.underlined = Some(underlined);
+        self
+    }
+
+    /// Set strikethrough
+    pub fn strikethrough(mut self, strikethrough: bool) -> TextComponent {
+        self.style.strikethrough = Some(strikethrough);
+        self
+    }
+
+    /// Set obfuscated
+    pub fn obfuscated(mut self, obfuscated: bool) -> TextComponent {
+        self.style.obfuscated = Some(obfuscated);
+        self
+    }
+
+    /// Set font
+    pub fn font(mut self, font: impl Into<String>) -> TextComponent {
+        self.style.font = Some(font.into());
+        self
+    }
+
+    /// Set text inserted on shift click
+    pub fn insertion(mut self, insertion: impl Into<String>) -> TextComponent {
+        self.style.insertion = Some(insertion.into());
+        self
+    }
+
+    /// Set click event
+    pub fn click(mut self, event: ClickEvent) -> TextComponent {
+        self.style.click_event = Some(event);
+        self
+    }
+
+    /// Set hover event
+    pub fn hover(mut self, event: HoverEvent) -> TextComponent {
+        self.style.hover_event = Some(event);
+        self
+    }
+
+    /// Add child component
+    pub fn extra(mut self, child: impl Into<TextComponent>) -> TextComponent {
+        self.extra.push(child.into());
+        self
+    }
+
+    /// Get text without formatting, translations are replaced with fallback or key
+    pub fn to_plain(&self) -> String {
+        let mut text = String::new();
+        self.walk(&Style::default(), &mut |content, _| text.push_str(&content));
+        text
+    }
+
+    /// Call `f` with plain text and inherited style of every component in tree
+    fn walk(&self, parent: &Style, f: &mut impl FnMut(String, &Style)) {
+        let style = self.style.inherit(parent);
+        let content = match &self.content {
+            Content::Text(text) => text.clone(),
+            Content::Translate { key, fallback, .. } => fallback.clone().unwrap_or(key.clone()),
+            Content::Keybind(key) => key.clone(),
+            Content::Score { .. } => String::new(),
+            Content::Selector { selector, .. } => selector.clone(),
+        };
+        f(content, &style);
+        for child in &self.extra {
+            child.walk(&style, f);
+        }
+    }
+
+    /// Parse text with legacy `§` formatting codes
+    pub fn from_legacy(text: &str) -> TextComponent {
+        TextComponent::from_legacy_with(text, '§')
+    }
+
+    /// Parse text with legacy formatting codes that start with `prefix`, like `&`
+    pub fn from_legacy_with(text: &str, prefix: char) -> TextComponent {
+        let mut parts = Vec::new();
+        let mut style = Style::default();
+        let mut buf = String::new();
+        let mut chars = text.chars();
+
+        let flush = |buf: &mut String, style: &Style, parts: &mut Vec<TextComponent>| {
+            if !buf.is_empty() {
+                let mut part = TextComponent::text(std::mem::take(buf));
+                part.style = style.clone();
+                parts.push(part);
+            }
+        };
+
+        while let Some(c) = chars.next() {
+            if c != prefix {
+                buf.push(c);
+                continue;
+            }
+
+            let Some(code) = chars.next() else {
+                buf.push(c);
+                break;
+            };
+
+            let code = code.to_ascii_lowercase();
+            let mut next = style.clone();
+            match code {
+                'k' => next.obfuscated = Some(true),
+                'l' => next.bold = Some(true),
+                'm' => next.strikethrough = Some(true),
+                'n' => next.underlined = Some(true),
+                'o' => next.italic = Some(true),
+                'r' => next = Style::default(),
+                code => match Color::from_legacy_code(code) {
+                    Some(color) => {
+                        next = Style {
+                            color: Some(color),
+                            ..Style::default()
+                        }
+                    }
+                    None => {
+                        buf.push(c);
+                        buf.push(code);
+                        continue;
+                    }
+                },
+            }
+
+            flush(&mut buf, &style, &mut parts);
+            style = next;
+        }
+        flush(&mut buf, &style, &mut parts);
+
+        match parts.len() {
+            0 => TextComponent::text(""),
+            1 => parts.remove(0),
+            _ => TextComponent {
+                extra: parts,
+                ..TextComponent::text("")
+            },
+        }
+    }
+
+    /// Get text with legacy `§` formatting codes, RGB colors are replaced with nearest named colors
+    pub fn to_legacy(&self) -> String {
+        let mut text = String::new();
+        let mut last = (None, String::new());
+
+        self.walk(&Style::default(), &mut |content, style| {
+            if content.is_empty() {
+                return;
+            }
+            let current = (style.color.map(|i| i.legacy_code()), style.legacy_decorations());
+            if current != last {
+                if current.0.is_some() || !last.1.is_empty() || last.0.is_some() {
+                    text.push('§');
+                    text.push(current.0.unwrap_or('r'));
+                }
+                for code in current.1.chars() {
+                    text.push('§');
+                    text.push(code);
+                }
+                last = current;
+            }
+            text.push_str(&content);
+        });
+
+        text
+    }
+
+    /// Convert component to JSON value
+    pub fn to_value(&self) -> Value {
+        let mut map = Map::new();
+
+        match &self.content {
+            Content::Text(text) => {
+                map.insert("text".to_string(), text.clone().into());
+            }
+            Content::Translate { key, fallback, with } => {
+                map.insert("translate".to_string(), key.clone().into());
+                if let Some(fallback) = fallback {
+                    map.insert("fallback".to_string(), fallback.clone().into());
+                }
+                if !with.is_empty() {
+                    map.insert("with".to_string(), with.iter().map(|i| i.to_value()).collect());
+                }
+            }
+            Content::Keybind(key) => {
+                map.insert("keybind".to_string(), key.clone().into());
+            }
+            Content::Score { name, objective } => {
+                let mut score = Map::new();
+                score.insert("name".to_string(), name.clone().into());
+                score.insert("objective".to_string(), objective.clone().into());
+                map.insert("score".to_string(), score.into());
+            }
+            Content::Selector { selector, separator } => {
+                map.insert("selector".to_string(), selector.clone().into());
+                if let Some(separator) = separator {
+                    map.insert("separator".to_string(), separator.to_value());
+                }
+            }
+        }
+
+        let style = &self.style;
+        if let Some(color) = style.color {
+            map.insert("color".to_string(), color.name().into());
+        }
+        for (name, value) in [
+            ("bold", style.bold),
+            ("italic", style.italic),
+            ("underlined", style.underlined),
+            ("strikethrough", style.strikethrough),
+            ("obfuscated", style.obfuscated),
+        ] {
+            if let Some(value) = value {
+                map.insert(name.to_string(), value.into());
+            }
+        }
+        if let Some(font) = &style.font {
+            map.insert("font".to_string(), font.clone().into());
+        }
+        if let Some(insertion) = &style.insertion {
+            map.insert("insertion".to_string(), insertion.clone().into());
+        }
+        if let Some(event) = &style.click_event {
+            map.insert("clickEvent".to_string(), click_to_value(event));
+        }
+        if let Some(event) = &style.hover_event {
+            map.insert("hoverEvent".to_string(), hover_to_value(event));
+        }
+
+        if !self.extra.is_empty() {
+            map.insert("extra".to_string(), self.extra.iter().map(|i| i.to_value()).collect());
+        }
+
+        Value::Object(map)
+    }
+
+    /// Read component from JSON value, accepts strings and arrays like vanilla
+    pub fn from_value(value: &Value) -> Result<TextComponent, ProtocolError> {
+        let map = match value {
+            Value::String(text) => return Ok(TextComponent::text(text.clone())),
+            Value::Number(_) | Value::Bool(_) => return Ok(TextComponent::text(value.to_string())),
+            Value::Array(list) => {
+                let Some((first, rest)) = list.split_first() else {
+                    return Err(ProtocolError::TextComponentError("empty component list"));
+                };
+                let mut component = TextComponent::from_value(first)?;
+                for i in rest {
+                    component.extra.push(TextComponent::from_value(i)?);
+                }
+                return Ok(component);
+            }
+            Value::Object(map) => map,
+            Value::Null => return Err(ProtocolError::TextComponentError("component is null")),
+        };
+
+        let content = if let Some(text) = map.get("text") {
+            Content::Text(match text {
+                Value::String(text) => text.clone(),
+                other => other.to_string(),
+            })
+        } else if let Some(key) = map.get("translate") {
+            Content::Translate {
+                key: as_string(key, "translate")?,
+                fallback: map.get("fallback").map(|i| as_string(i, "fallback")).transpose()?,
+                with: match map.get("with") {
+                    Some(Value::Array(list)) => list
+                        .iter()
+                        .map(TextComponent::from_value)
+                        .collect::<Result<_, _>>()?,
+                    Some(_) => return Err(ProtocolError::TextComponentError("with is not a list")),
+                    None => Vec::new(),
+                },
+            }
+        } else if let Some(key) = map.get("keybind") {
+            Content::Keybind(as_string(key, "keybind")?)
+        } else if let Some(score) = map.get("score") {
+            Content::Score {
+                name: as_string(field(score, "name")?, "name")?,
+                objective: as_string(field(score, "objective")?, "objective")?,
+            }
+        } else if let Some(selector) = map.get("selector") {
+            Content::Selector {
+                selector: as_string(selector, "selector")?,
+                separator: map
+                    .get("separator")
+                    .map(TextComponent::from_value)
+                    .transpose()?
+                    .map(Box::new),
+            }
+        } else if let Some(value) = map.get("") {
+            // element of mixed NBT list
+            return TextComponent::from_value(value);
+        } else {
+            return Err(ProtocolError::TextComponentError("unknown component content"));
+        };
+
+        let style = Style {
+            color: map
+                .get("color")
+                .map(|i| {
+                    Color::parse(&as_string(i, "color")?)
+                        .ok_or(ProtocolError::TextComponentError("invalid color"))
+                })
+                .transpose()?,
+            bold: map.get("bold").map(|i| as_bool(i, "bold")).transpose()?,
+            italic: map.get("italic").map(|i| as_bool(i, "italic")).transpose()?,
+            underlined: map.get("underlined").map(|i| as_bool(i, "underlined")).transpose()?,
+            strikethrough: map
+                .get("strikethrough")
+                .map(|i| as_bool(i, "strikethrough"))
+                .transpose()?,
+            obfuscated: map.get("obfuscated").map(|i| as_bool(i, "obfuscated")).transpose()?,
+            font: map.get("font").map(|i| as_string(i, "font")).transpose()?,
+            insertion: map.get("insertion").map(|i| as_string(i, "insertion")).transpose()?,
+            click_event: map
+                .get("clickEvent")
+                .or(map.get("click_event"))
+                .map(click_from_value)
+                .transpose()?,
+            hover_event: map
+                .get("hoverEvent")
+                .or(map.get("hover_event"))
+                .map(hover_from_value)
+                .transpose()?,
+        };
+
+        let extra = match map.get("extra") {
+            Some(Value::Array(list)) => list
+                .iter()
+                .map(TextComponent::from_value)
+                .collect::<Result<_, _>>()?,
+            Some(_) => return Err(ProtocolError::TextComponentError("extra is not a list")),
+            None => Vec::new(),
+        };
+
+        Ok(TextComponent {
+            content,
+            style,
+            extra,
+        })
+    }
+
+    /// Convert component to JSON string
+    pub fn to_json(&self) -> String {
+        self.to_value().to_string()
+    }
+
+    /// Parse component from JSON string
+    pub fn from_json(json: &str) -> Result<TextComponent, ProtocolError> {
+        let value: Value = serde_json::from_str(json).map_err(ProtocolError::JsonError)?;
+        TextComponent::from_value(&value)
+    }
+
+    /// Convert component to NBT, plain text without style is written as string tag
+    pub fn to_nbt(&self) -> Nbt {
+        match &self.content {
+            Content::Text(text) if self.style == Style::default() && self.extra.is_empty() => {
+                Nbt::String(text.clone())
+            }
+            _ => value_to_nbt(&self.to_value()),
+        }
+    }
+
+    /// Read component from NBT
+    pub fn from_nbt(nbt: &Nbt) -> Result<TextComponent, ProtocolError> {
+        TextComponent::from_value(&nbt_to_value(nbt))
+    }
+}
+
+impl Default for TextComponent {
+    fn default() -> Self {
+        TextComponent::text("")
+    }
+}
+
+impl fmt::Display for TextComponent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_plain())
+    }
+}
+
+impl From<&str> for TextComponent {
+    fn from(text: &str) -> Self {
+        TextComponent::text(text)
+    }
+}
+
+impl From<String> for TextComponent {
+    fn from(text: String) -> Self {
+        TextComponent::text(text)
+    }
+}
+
+fn field<'a>(value: &'a Value, name: &'static str) -> Result<&'a Value, ProtocolError> {
+    value.get(name).ok_or(ProtocolError::TextComponentError(name))
+}
+
+fn as_string(value: &Value, name: &'static str) -> Result<String, ProtocolError> {
+    match value {
+        Value::String(i) => Ok(i.clone()),
+        _ => Err(ProtocolError::TextComponentError(name)),
+    }
+}
+
+fn as_bool(value: &Value, name: &'static str) -> Result<bool, ProtocolError> {
+    match value {
+        Value::Bool(i) => Ok(*i),
+        Value::Number(i) => Ok(i.as_i64() != Some(0)),
+        _ => Err(ProtocolError::TextComponentError(name)),
+    }
+}
+
+fn as_int(value: &Value, name: &'static str) -> Result<i32, ProtocolError> {
+    match value {
+        Value::Number(i) => i.as_i64().map(|i| i as i32),
+        Value::String(i) => i.parse().ok(),
+        _ => None,
+    }
+    .ok_or(ProtocolError::TextComponentError(name))
+}
+
+fn click_to_value(event: &ClickEvent) -> Value {
+    let (action, value) = match event {
+        ClickEvent::OpenUrl(i) => ("open_url", i.clone()),
+        ClickEvent::OpenFile(i) => ("open_file", i.clone()),
+        ClickEvent::RunCommand(i) => ("run_command", i.clone()),
+        ClickEvent::SuggestCommand(i) => ("suggest_command", i.clone()),
+        ClickEvent::ChangePage(i) => ("change_page", i.to_string()),
+        ClickEvent::CopyToClipboard(i) => ("copy_to_clipboard", i.clone()),
+    };
+    let mut map = Map::new();
+    map.insert("action".to_string(), action.into());
+    map.insert("value".to_string(), value.into());
+    Value::Object(map)
+}
+
+fn click_from_value(value: &Value) -> Result<ClickEvent, ProtocolError> {
+    let action = as_string(field(value, "action")?, "action")?;
+    let value = field(value, "value")?;
+    Ok(match action.as_str() {
+        "open_url" => ClickEvent::OpenUrl(as_string(value, "value")?),
+        "open_file" => ClickEvent::OpenFile(as_string(value, "value")?),
+        "run_command" => ClickEvent::RunCommand(as_string(value, "value")?),
+        "suggest_command" => ClickEvent::SuggestCommand(as_string(value, "value")?),
+        "change_page" => ClickEvent::ChangePage(as_int(value, "value")?),
+        "copy_to_clipboard" => ClickEvent::CopyToClipboard(as_string(value, "value")?),
+        _ => return Err(ProtocolError::TextComponentError("unknown click action")),
+    })
+}
+
+fn hover_to_value(event: &HoverEvent) -> Value {
+    let (action, contents) = match event {
+        HoverEvent::ShowText(text) => ("show_text", text.to_value()),
+        HoverEvent::ShowItem { id, count } => {
+            let mut map = Map::new();
+            map.insert("id".to_string(), id.clone().into());
+            map.insert("count".to_string(), (*count).into());
+            ("show_item", Value::Object(map))
+        }
+        HoverEvent::ShowEntity {
+            entity_type,
+            id,
+            name,
+        } => {
+            let mut map = Map::new();
+            map.insert("type".to_string(), entity_type.clone().into());
+            map.insert("id".to_string(), id.to_string().into());
+            if let Some(name) = name {
+                map.insert("name".to_string(), name.to_value());
+            }
+            ("show_entity", Value::Object(map))
+        }
+    };
+    let mut map = Map::new();
+    map.insert("action".to_string(), action.into());
+    map.insert("contents".to_string(), contents);
+    Value::Object(map)
+}
+
+fn hover_from_value(value: &Value) -> Result<HoverEvent, ProtocolError> {
+    let action = as_string(field(value, "action")?, "action")?;
+    let contents = field(value, "contents")?;
+    Ok(match action.as_str() {
+        "show_text" => HoverEvent::ShowText(Box::new(TextComponent::from_value(contents)?)),
+        "show_item" => match contents {
+            Value::String(id) => HoverEvent::ShowItem {
+                id: id.clone(),
+                count: 1,
+            },
+            _ => HoverEvent::ShowItem {
+                id: as_string(field(contents, "id")?, "id")?,
+                count: contents
+                    .get("count")
+                    .map(|i| as_int(i, "count"))
+                    .transpose()?
+                    .unwrap_or(1),
+            },
+        },
+        "show_entity" => HoverEvent::ShowEntity {
+            entity_type: as_string(field(contents, "type")?, "type")?,
+            id: uuid_from_value(field(contents, "id")?)?,
+            name: contents
+                .get("name")
+                .map(TextComponent::from_value)
+                .transpose()?
+                .map(Box::new),
+        },
+        _ => return Err(ProtocolError::TextComponentError("unknown hover action")),
+    })
+}
+
+/// UUID is string in JSON and int array in NBT
+fn uuid_from_value(value: &Value) -> Result<Uuid, ProtocolError> {
+    match value {
+        Value::String(i) => Uuid::parse_str(i).ok(),
+        Value::Array(list) if list.len() == 4 => {
+            let mut bits: u128 = 0;
+            for i in list {
+                bits = (bits << 32) | (i.as_i64().unwrap_or_default() as u32 as u128);
+            }
+            Some(Uuid::from_u128(bits))
+        }
+        _ => None,
+    }
+    .ok_or(ProtocolError::TextComponentError("id"))
+}
+
+fn value_to_nbt(value: &Value) -> Nbt {
+    match value {
+        Value::Null => Nbt::End,
+        Value::Bool(i) => Nbt::Byte(*i as i8),
+        Value::Number(i) => match (i.as_i64(), i.as_f64()) {
+            (Some(i), _) if i32::try_from(i).is_ok() => Nbt::Int(i as i32),
+            (Some(i), _) => Nbt::Long(i),
+            (None, Some(i)) => Nbt::Double(i),
+            (None, None) => Nbt::End,
+        },
+        Value::String(i) => Nbt::String(i.clone()),
+        Value::Array(list) => {
+            let list: Vec<Nbt> = list.iter().map(value_to_nbt).collect();
+            let tag_id = list.first().map(Nbt::tag_id).unwrap_or(0);
+            if list.iter().all(|i| i.tag_id() == tag_id) {
+                Nbt::List(list)
+            } else {
+                // NBT lists can't mix types, so elements are wrapped like vanilla does
+                Nbt::List(
+                    list.into_iter()
+                        .map(|i| match i {
+                            Nbt::Compound(map) => Nbt::Compound(map),
+                            i => Nbt::Compound(BTreeMap::from([(String::new(), i)])),
+                        })
+                        .collect(),
+                )
+            }
+        }
+        Value::Object(map) => Nbt::Compound(
+            map.iter()
+                .map(|(k, v)| (k.clone(), value_to_nbt(v)))
+                .collect(),
+        ),
+    }
+}
+
+fn nbt_to_value(nbt: &Nbt) -> Value {
+    match nbt {
+        Nbt::End => Value::Null,
+        Nbt::Byte(i) => Value::Bool(*i != 0),
+        Nbt::Short(i) => (*i).into(),
+        Nbt::Int(i) => (*i).into(),
+        Nbt::Long(i) => (*i).into(),
+        Nbt::Float(i) => Number::from_f64(*i as f64).map(Value::Number).unwrap_or(Value::Null),
+        Nbt::Double(i) => Number::from_f64(*i).map(Value::Number).unwrap_or(Value::Null),
+        Nbt::ByteArray(list) => list.iter().map(|i| Value::from(*i)).collect(),
+        Nbt::String(i) => Value::String(i.clone()),
+        Nbt::List(list) => list.iter().map(nbt_to_value).collect(),
+        Nbt::Compound(map) => Value::Object(
+            map.iter()
+                .map(|(k, v)| (k.clone(), nbt_to_value(v)))
+                .collect(),
+        ),
+        Nbt::IntArray(list) => list.iter().map(|i| Value::from(*i)).collect(),
+        Nbt::LongArray(list) => list.iter().map(|i| Value::from(*i)).collect(),
+    }
+}
@@ -1,4 +1,6 @@
-//! VarInt reading helper 
+//! Zigzag encoding helper for signed VarInts
+//!
+//! Vanilla Minecraft does not use zigzag encoding, see [`DataReader::read_varint`](crate::DataReader::read_varint)
 
 pub trait Zigzag<T> {
     fn zigzag(&self) -> T;
@@ -55,7 +57,7 @@ impl Zigzag<i128> for u128 {
 }
 impl Zigzag<usize> for isize {
     fn zigzag(&self) -> usize {
-        ((self << 1) ^ (self >> std::mem::size_of::<usize>() - 1)) as usize
+        ((self << 1) ^ (self >> (std::mem::size_of::<usize>() * 8 - 1))) as usize
     }
 }
 impl Zigzag<isize> for usize {